@@ -0,0 +1,115 @@
+//! Approximate model counting
+//!
+//! An ApproxMC style counter. Random XOR constraints split the models into cells of roughly equal size, small enough to count by bounded enumeration.
+//! The size of one cell multiplied by the number of cells estimates the total, and the median of repeated estimates gives a probabilistic guarantee.
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{encode, projected_models, Count, Formula, Lit, Var, VarPool};
+
+/// Estimates the number of assignments to the variables of `formula` that satisfy it.
+///
+/// See `approx_count_projected_models`.
+pub fn approx_count_models(formula: &Formula, epsilon: f64, delta: f64, seed: u64) -> Count {
+    approx_count_projected_models(formula, &formula.vars(), epsilon, delta, seed)
+}
+
+/// Estimates the number of distinct assignments to `vars` that extend to a model of `formula`.
+///
+/// With probability at least `1 - delta`, the estimate is within a factor of `1 + epsilon` of the exact count.
+/// Counts small enough to enumerate are exact. The same `seed` always gives the same estimate.
+pub fn approx_count_projected_models(
+    formula: &Formula,
+    vars: &[Var],
+    epsilon: f64,
+    delta: f64,
+    seed: u64,
+) -> Count {
+    assert!(epsilon > 0.0, "epsilon must be positive");
+    assert!(delta > 0.0 && delta < 1.0, "delta must be between 0 and 1");
+
+    let mut vars = vars.to_vec();
+    vars.sort();
+    vars.dedup();
+
+    let threshold = (1.0 + 9.84 * (1.0 + epsilon / (1.0 + epsilon)) * (1.0 + 1.0 / epsilon).powi(2))
+        .ceil() as usize;
+    let iterations = (17.0 * (3.0 / delta).log2()).ceil() as usize;
+
+    let cell_size = |formula: &Formula| projected_models(formula, &vars).take(threshold).count();
+
+    let total = cell_size(formula);
+
+    if total < threshold {
+        return Count::from(total as u64);
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut estimates = Vec::with_capacity(iterations);
+    let mut previous = 1;
+
+    for _ in 0..iterations {
+        let hashes: Vec<Hash> = (0..vars.len())
+            .map(|_| random_hash(&vars, &mut rng))
+            .collect();
+
+        // The size of the cell selected by the first `count` hashes
+        let hashed_size = |count: usize| cell_size(&add_hashes(formula, &vars, &hashes[..count]));
+
+        // Find the fewest hashes giving a small enough cell, starting from the previous iteration's answer
+        let mut count = previous.min(vars.len());
+        let mut size = hashed_size(count);
+
+        if size < threshold {
+            while count > 1 {
+                let larger = hashed_size(count - 1);
+
+                if larger >= threshold {
+                    break;
+                }
+
+                count -= 1;
+                size = larger;
+            }
+        } else {
+            while size >= threshold && count < vars.len() {
+                count += 1;
+                size = hashed_size(count);
+            }
+        }
+
+        estimates.push(&Count::from(size as u64) * &Count::pow2(count));
+        previous = count;
+    }
+
+    estimates.sort();
+    estimates.swap_remove(estimates.len() / 2)
+}
+
+/// A random XOR constraint: the literals whose parity is constrained, and the required parity.
+pub(crate) type Hash = (Vec<Lit>, bool);
+
+/// Generates an XOR constraint over a random subset of `vars` with a random parity.
+pub(crate) fn random_hash(vars: &[Var], rng: &mut impl Rng) -> Hash {
+    let lits = vars
+        .iter()
+        .filter(|_| rng.random_bool(0.5))
+        .map(|var| var.positive())
+        .collect();
+
+    (lits, rng.random_bool(0.5))
+}
+
+/// Returns a copy of `formula` restricted to the cell selected by `hashes`.
+///
+/// `vars` must contain every variable used by the hashes.
+pub(crate) fn add_hashes(formula: &Formula, vars: &[Var], hashes: &[Hash]) -> Formula {
+    let mut pool = VarPool::after(formula.max_var().max(vars.iter().max().copied()));
+    let mut hashed = formula.clone();
+
+    for (lits, parity) in hashes {
+        encode::xor(lits, *parity, &mut pool, &mut hashed);
+    }
+
+    hashed
+}
@@ -0,0 +1,137 @@
+//! Backbones
+//!
+//! The backbone of a satisfiable formula is the set of literals true in every model.
+//! Candidates start as the literals of one model, and each further model removes the candidates it falsifies.
+//! Candidates are tested in chunks: a single solve with the negations of a chunk as assumptions either finds a model that removes the whole chunk, or fails with a core of the chunk.
+//! A core of one literal proves its complement is in the backbone, while a larger core is resolved by testing its literals one at a time.
+
+use crate::{Clause, Formula, Lit, SolveResult, Solver, SolverConfig, Var};
+
+/// Configuration for `backbone`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackboneConfig {
+    /// Candidates tested together by one solver call.
+    pub chunk_size: usize,
+    /// The most solver calls to make, or unlimited if `None`.
+    /// Candidates not decided within the budget are reported as undecided.
+    pub max_calls: Option<usize>,
+    /// Configuration of the incremental solver used for every call.
+    pub solver: SolverConfig,
+}
+
+impl Default for BackboneConfig {
+    fn default() -> BackboneConfig {
+        BackboneConfig {
+            chunk_size: 32,
+            max_calls: None,
+            solver: SolverConfig::default(),
+        }
+    }
+}
+
+/// The result of `backbone`, partitioning the variables of a satisfiable formula.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Backbone {
+    lits: Vec<Lit>,
+    free: Vec<Var>,
+    undecided: Vec<Lit>,
+    calls: usize,
+}
+
+impl Backbone {
+    /// Literals true in every model, sorted.
+    pub fn lits(&self) -> &[Lit] {
+        &self.lits
+    }
+
+    /// Variables that take both values in some models, sorted.
+    pub fn free(&self) -> &[Var] {
+        &self.free
+    }
+
+    /// Candidates that are true in every model found, but were not proven to be in the backbone before the call budget ran out, sorted.
+    pub fn undecided(&self) -> &[Lit] {
+        &self.undecided
+    }
+
+    /// The number of solver calls made.
+    pub fn calls(&self) -> usize {
+        self.calls
+    }
+
+    /// Returns `true` if every variable was decided within the call budget.
+    pub fn is_complete(&self) -> bool {
+        self.undecided.is_empty()
+    }
+}
+
+/// Computes the backbone of `formula` with incremental solves under assumptions.
+///
+/// Returns `None` if the formula is unsatisfiable, or if the budget runs out before a first model is found.
+pub fn backbone(formula: &Formula, config: &BackboneConfig) -> Option<Backbone> {
+    let mut solver = Solver::from_formula(formula, config.solver.clone());
+    let vars = formula.vars();
+    let mut calls = 0;
+
+    if config.max_calls == Some(0) {
+        return None;
+    }
+
+    let model = solver.solve().model()?.completed(&vars);
+    calls += 1;
+
+    let mut candidates: Vec<Lit> = model.lits();
+    let mut lits = Vec::new();
+    let chunk_size = config.chunk_size.max(1);
+    let mut chunk_len = chunk_size;
+
+    while !candidates.is_empty() && config.max_calls.is_none_or(|max| calls < max) {
+        let chunk = chunk_len.min(candidates.len());
+        let assumptions: Vec<Lit> = candidates[..chunk].iter().map(|lit| !*lit).collect();
+
+        let result = solver.solve_with_assumptions(&assumptions);
+        calls += 1;
+
+        match result {
+            // The model falsifies at least every literal of the chunk
+            SolveResult::Sat(model) => {
+                candidates.retain(|lit| model.evaluate(lit) == Some(true));
+                chunk_len = chunk_size;
+            }
+            SolveResult::Unsat => match solver.failed_assumptions() {
+                [negation] => {
+                    let lit = !*negation;
+                    candidates.retain(|other| *other != lit);
+                    solver.add_clause(&Clause::from(vec![lit]));
+                    lits.push(lit);
+                    chunk_len = chunk_size;
+                }
+                // The backbone literals added as units keep the formula satisfiable
+                [] => unreachable!("empty core of a satisfiable formula"),
+                core => {
+                    // The formula only implies that some literal of the core is true, so test them one at a time
+                    let core: Vec<Lit> = core.iter().map(|negation| !*negation).collect();
+                    candidates.retain(|lit| !core.contains(lit));
+                    candidates.splice(0..0, core);
+                    chunk_len = 1;
+                }
+            },
+            SolveResult::Unknown => break,
+        }
+    }
+
+    lits.sort();
+    candidates.sort();
+
+    let free = vars
+        .into_iter()
+        .filter(|var| !lits.iter().chain(&candidates).any(|lit| lit.var() == *var))
+        .collect();
+
+    Some(Backbone {
+        lits,
+        free,
+        undecided: candidates,
+        calls,
+    })
+}
@@ -0,0 +1,1738 @@
+//! Conflict-driven clause learning
+//!
+//! A configurable CDCL solver, with two watched literals, first UIP clause learning, VSIDS branching and restarts.
+//! Parity constraints are propagated natively by Gauss-Jordan elimination, and cardinality constraints by counting their true literals.
+//! Both explain their propagations and conflicts as clauses, so that conflict analysis is unchanged.
+//! Between restarts the solver periodically inprocesses its clauses: failed literal probing with hyper-binary resolution, subsumption, and vivification.
+//! Unlike `solve`, a `Solver` is incremental: clauses can be added between calls, and each call can take assumptions.
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    preprocess::ClauseDb, subsume::subsume_all, Assignment, Cardinality, Clause, Formula, Lit,
+    SolveResult, SubsumptionStats, Var, Xor,
+};
+
+/// The value chosen for a decision variable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Polarity {
+    /// The value the variable last had, initially `false`.
+    Saved,
+    /// Always `false`.
+    Negative,
+    /// A random value.
+    Random,
+}
+
+/// When the solver abandons its decisions and starts again from the top level, keeping learned clauses.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Restarts {
+    Never,
+    /// After `unit` times the next term of the Luby sequence (1, 1, 2, 1, 1, 2, 4, ...) in conflicts.
+    Luby {
+        unit: u64,
+    },
+    /// After `first` conflicts, then `factor` times as many as the previous restart.
+    Geometric {
+        first: u64,
+        factor: f64,
+    },
+}
+
+/// Configuration for a `Solver`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolverConfig {
+    pub seed: u64,
+    /// The factor applied to variable activities after each conflict, between 0 and 1.
+    pub var_decay: f64,
+    /// The probability that a decision picks a random variable instead of the most active one.
+    pub random_var_freq: f64,
+    pub polarity: Polarity,
+    pub restarts: Restarts,
+    /// The number of restarts between rounds of inprocessing, or `None` to never inprocess.
+    pub inprocess_interval: Option<u64>,
+    /// The propagations each inprocessing technique may use, as a fraction of those used by search since the last round.
+    pub inprocess_effort: f64,
+}
+
+impl Default for SolverConfig {
+    fn default() -> SolverConfig {
+        SolverConfig {
+            seed: 0,
+            var_decay: 0.95,
+            random_var_freq: 0.0,
+            polarity: Polarity::Saved,
+            restarts: Restarts::Luby { unit: 100 },
+            inprocess_interval: Some(8),
+            inprocess_effort: 0.1,
+        }
+    }
+}
+
+/// Counters describing the work done by a `Solver`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SolverStats {
+    pub decisions: u64,
+    pub propagations: u64,
+    pub conflicts: u64,
+    pub restarts: u64,
+    pub learned_clauses: u64,
+    pub deleted_clauses: u64,
+    pub imported_clauses: u64,
+    pub subsumption: SubsumptionStats,
+    /// Literals found by probing to lead to a conflict, whose complements were then assigned.
+    pub failed_literals: u64,
+    pub hyper_binary_resolvents: u64,
+    /// Clauses shortened by vivification.
+    pub vivified_clauses: u64,
+    /// Literals removed by vivification.
+    pub vivified_literals: u64,
+    /// Literals assigned by Gauss-Jordan elimination of the parity constraints.
+    pub xor_propagations: u64,
+    pub xor_conflicts: u64,
+    /// Literals assigned false because a cardinality constraint already had as many true literals as allowed.
+    pub cardinality_propagations: u64,
+    pub cardinality_conflicts: u64,
+}
+
+/// Learned clauses published by solvers working on the same formula.
+#[derive(Debug, Default)]
+pub(crate) struct ClauseExchange {
+    /// Each clause and the id of the solver that learned it.
+    clauses: Mutex<Vec<(usize, Vec<Lit>)>>,
+}
+
+/// A solver's connection to a `ClauseExchange`.
+#[derive(Debug)]
+struct ExchangeLink {
+    exchange: Arc<ClauseExchange>,
+    id: usize,
+    /// Only learned clauses with at most this many literals are exported.
+    max_len: usize,
+    /// The number of exchanged clauses already seen.
+    cursor: usize,
+}
+
+#[derive(Debug, Clone)]
+struct CardinalityData {
+    lits: Vec<Lit>,
+    bound: usize,
+    /// The number of literals currently true.
+    true_count: usize,
+}
+
+#[derive(Debug, Clone)]
+struct ClauseData {
+    /// The first two literals are watched.
+    lits: Vec<Lit>,
+    learned: bool,
+    deleted: bool,
+    /// The number of distinct decision levels among the literals when learned.
+    lbd: usize,
+    /// Whether vivification has been tried on this clause.
+    vivified: bool,
+}
+
+/// An incremental CDCL SAT solver.
+#[derive(Debug)]
+pub struct Solver {
+    config: SolverConfig,
+    rng: StdRng,
+    /// `false` once the clauses are known to be unsatisfiable without assumptions.
+    ok: bool,
+
+    clauses: Vec<ClauseData>,
+    /// Clause indices watching each literal, indexed by `Lit::code`.
+    watches: Vec<Vec<usize>>,
+    /// Whether each variable occurs in an added clause.
+    used: Vec<bool>,
+
+    values: Vec<Option<bool>>,
+    levels: Vec<usize>,
+    reasons: Vec<Option<usize>>,
+    trail: Vec<Lit>,
+    /// The trail length at the start of each decision level.
+    trail_limits: Vec<usize>,
+    /// The trail position of the next literal to propagate.
+    queue_head: usize,
+
+    activity: Vec<f64>,
+    activity_increment: f64,
+    heap: VarHeap,
+    saved_phases: Vec<bool>,
+    seen: Vec<bool>,
+
+    /// Parity constraints as bits over the columns in `xor_vars`, linearly independent.
+    xor_rows: Vec<(Vec<u64>, bool)>,
+    /// The pivot column of each row of `xor_rows`, which is set in no other row.
+    ///
+    /// Propagation moves the pivot of a row off an assigned variable when the row has an unassigned one, so the matrix is kept between calls rather than eliminated again.
+    xor_pivots: Vec<usize>,
+    /// The variable index of each column of `xor_rows`.
+    xor_vars: Vec<usize>,
+    /// The column of each variable in `xor_rows`, indexed by variable.
+    xor_columns: Vec<Option<usize>>,
+    /// Slots in `clauses` of explanations that are no longer reasons, for reuse.
+    free_explanations: Vec<usize>,
+
+    cardinalities: Vec<CardinalityData>,
+    /// The cardinality constraints containing each literal, indexed by `Lit::code`.
+    cardinality_watches: Vec<Vec<usize>>,
+
+    max_learned: usize,
+    learned_count: usize,
+    restart_count: u64,
+    /// The next variable to probe.
+    probe_cursor: usize,
+    /// The propagation count at the end of the last round of inprocessing.
+    inprocess_propagations: u64,
+
+    failed_assumptions: Vec<Lit>,
+    interrupt: Option<Arc<AtomicBool>>,
+    exchange: Option<ExchangeLink>,
+    stats: SolverStats,
+}
+
+impl Solver {
+    /// Creates a solver with no clauses.
+    pub fn new(config: SolverConfig) -> Solver {
+        Solver {
+            rng: StdRng::seed_from_u64(config.seed),
+            config,
+            ok: true,
+            clauses: Vec::new(),
+            watches: Vec::new(),
+            used: Vec::new(),
+            values: Vec::new(),
+            levels: Vec::new(),
+            reasons: Vec::new(),
+            trail: Vec::new(),
+            trail_limits: Vec::new(),
+            queue_head: 0,
+            activity: Vec::new(),
+            activity_increment: 1.0,
+            heap: VarHeap::default(),
+            saved_phases: Vec::new(),
+            seen: Vec::new(),
+            xor_rows: Vec::new(),
+            xor_pivots: Vec::new(),
+            xor_vars: Vec::new(),
+            xor_columns: Vec::new(),
+            free_explanations: Vec::new(),
+            cardinalities: Vec::new(),
+            cardinality_watches: Vec::new(),
+            max_learned: 2000,
+            learned_count: 0,
+            restart_count: 0,
+            probe_cursor: 0,
+            inprocess_propagations: 0,
+            failed_assumptions: Vec::new(),
+            interrupt: None,
+            exchange: None,
+            stats: SolverStats::default(),
+        }
+    }
+
+    /// Creates a solver containing the clauses of `formula`.
+    pub fn from_formula(formula: &Formula, config: SolverConfig) -> Solver {
+        let mut solver = Solver::new(config);
+        solver.add_formula(formula);
+
+        solver
+    }
+
+    /// Adds every clause of `formula`.
+    pub fn add_formula(&mut self, formula: &Formula) {
+        for clause in formula.clauses() {
+            self.add_clause(clause);
+        }
+
+        for xor in formula.xors() {
+            self.add_xor(xor);
+        }
+
+        for cardinality in formula.cardinalities() {
+            self.add_cardinality(cardinality);
+        }
+    }
+
+    /// Adds a cardinality constraint, which applies to every later call to `solve`.
+    pub fn add_cardinality(&mut self, cardinality: &Cardinality) {
+        self.cancel_until(0);
+
+        let lits = cardinality.literals();
+        let bound = cardinality.bound();
+        let index = self.cardinalities.len();
+
+        for lit in &lits {
+            self.ensure_var(lit.var());
+            self.used[lit.index()] = true;
+            self.heap.insert(lit.index(), &self.activity);
+            self.cardinality_watches[lit.code()].push(index);
+        }
+
+        let true_count = lits
+            .iter()
+            .filter(|lit| self.value(**lit) == Some(true))
+            .count();
+
+        // Enqueueing and propagation update the counts of watching constraints, so this one must already be stored
+        self.cardinalities.push(CardinalityData {
+            lits: lits.clone(),
+            bound,
+            true_count,
+        });
+
+        // Literals already true at the top level may leave no room for the rest
+        if true_count > bound {
+            self.ok = false;
+        } else if true_count == bound {
+            for lit in &lits {
+                if self.value(*lit).is_none() {
+                    self.enqueue(!*lit, None);
+                }
+            }
+
+            if self.propagate().is_some() {
+                self.ok = false;
+            }
+        }
+    }
+
+    /// Adds a parity constraint, which applies to every later call to `solve`.
+    pub fn add_xor(&mut self, xor: &Xor) {
+        self.cancel_until(0);
+
+        let mut row = Vec::new();
+
+        for var in xor.vars() {
+            self.ensure_var(var);
+            self.used[var.index()] = true;
+            self.heap.insert(var.index(), &self.activity);
+
+            let column = *self.xor_columns[var.index()].get_or_insert_with(|| {
+                self.xor_vars.push(var.index());
+                self.xor_vars.len() - 1
+            });
+
+            if row.len() <= column / 64 {
+                row.resize(column / 64 + 1, 0);
+            }
+
+            row[column / 64] |= 1 << (column % 64);
+        }
+
+        self.xor_rows.push((row, xor.parity()));
+
+        // Keep the rows independent, so that elimination during search never produces an empty row
+        let words = self.xor_vars.len().div_ceil(64);
+
+        for (bits, _) in &mut self.xor_rows {
+            bits.resize(words, 0);
+        }
+
+        let rank = eliminate(&mut self.xor_rows, 0..self.xor_vars.len());
+
+        if self.xor_rows[rank..].iter().any(|(_, parity)| *parity) {
+            self.ok = false;
+        }
+
+        self.xor_rows.truncate(rank);
+
+        // Elimination in column order leaves each row's pivot as its lowest column
+        self.xor_pivots = self
+            .xor_rows
+            .iter()
+            .map(|(bits, _)| columns(bits).next().unwrap())
+            .collect();
+    }
+
+    /// Adds a clause, which applies to every later call to `solve`.
+    pub fn add_clause(&mut self, clause: &Clause) {
+        self.cancel_until(0);
+
+        let mut lits = clause.literals();
+
+        for lit in &lits {
+            self.ensure_var(lit.var());
+            self.used[lit.index()] = true;
+            self.heap.insert(lit.index(), &self.activity);
+        }
+
+        lits.sort();
+        lits.dedup();
+
+        // Tautologies and clauses already satisfied at the top level are redundant
+        if lits.windows(2).any(|pair| pair[0] == !pair[1])
+            || lits.iter().any(|lit| self.value(*lit) == Some(true))
+        {
+            return;
+        }
+
+        lits.retain(|lit| self.value(*lit).is_none());
+        self.add_clause_at_top(lits, false);
+    }
+
+    /// Stops any running or future call to `solve` when `flag` is set, returning `SolveResult::Unknown`.
+    pub fn set_interrupt(&mut self, flag: Arc<AtomicBool>) {
+        self.interrupt = Some(flag);
+    }
+
+    /// Counters describing the work done so far.
+    pub fn stats(&self) -> &SolverStats {
+        &self.stats
+    }
+
+    /// After `solve_with_assumptions` returns `SolveResult::Unsat`, a subset of the assumptions that cannot all be true.
+    ///
+    /// Empty if the clauses are unsatisfiable without any assumptions.
+    pub fn failed_assumptions(&self) -> &[Lit] {
+        &self.failed_assumptions
+    }
+
+    /// Searches for an assignment satisfying every added clause.
+    pub fn solve(&mut self) -> SolveResult {
+        self.solve_with_assumptions(&[])
+    }
+
+    /// Searches for an assignment satisfying every added clause in which every literal in `assumptions` is true.
+    ///
+    /// A model assigns every variable that occurs in an added clause or in `assumptions`.
+    pub fn solve_with_assumptions(&mut self, assumptions: &[Lit]) -> SolveResult {
+        self.failed_assumptions.clear();
+
+        for lit in assumptions {
+            self.ensure_var(lit.var());
+        }
+
+        if !self.ok {
+            return SolveResult::Unsat;
+        }
+
+        let result = self.search(assumptions);
+        self.cancel_until(0);
+
+        result
+    }
+
+    /// Searches as `solve_with_assumptions` does, with `clause` added for this call only.
+    ///
+    /// The clause is added with the complement of `activation`, which must be a literal of a variable in no other clause.
+    /// It holds while `activation` is assumed during the search, and is satisfied for good by adding the complement as a unit clause afterwards.
+    pub(crate) fn solve_with_temporary_clause(
+        &mut self,
+        clause: &[Lit],
+        activation: Lit,
+        assumptions: &[Lit],
+    ) -> SolveResult {
+        let mut guarded = clause.to_vec();
+        guarded.push(!activation);
+        self.add_clause(&guarded.into());
+
+        let mut assumptions = assumptions.to_vec();
+        assumptions.push(activation);
+
+        let result = self.solve_with_assumptions(&assumptions);
+        self.add_clause(&Clause::from(vec![!activation]));
+
+        result
+    }
+
+    fn search(&mut self, assumptions: &[Lit]) -> SolveResult {
+        let mut restart_limit = self.restart_limit();
+        let mut conflicts_since_restart = 0;
+
+        loop {
+            if let Some(conflict) = self.propagate_all() {
+                self.stats.conflicts += 1;
+                conflicts_since_restart += 1;
+
+                if self.decision_level() == 0 {
+                    self.ok = false;
+                    return SolveResult::Unsat;
+                }
+
+                let (learned, level) = self.analyse(conflict);
+                self.release_conflict(conflict);
+
+                self.cancel_until(level);
+                self.learn(learned);
+
+                self.activity_increment /= self.config.var_decay;
+
+                continue;
+            }
+
+            if self
+                .interrupt
+                .as_ref()
+                .is_some_and(|flag| flag.load(Ordering::Relaxed))
+            {
+                return SolveResult::Unknown;
+            }
+
+            if restart_limit.is_some_and(|limit| conflicts_since_restart >= limit) {
+                self.cancel_until(0);
+                self.restart_count += 1;
+                self.stats.restarts += 1;
+
+                restart_limit = self.restart_limit();
+                conflicts_since_restart = 0;
+
+                if !self.import_clauses() {
+                    return SolveResult::Unsat;
+                }
+
+                if self
+                    .config
+                    .inprocess_interval
+                    .is_some_and(|interval| self.restart_count.is_multiple_of(interval))
+                    && !self.inprocess()
+                {
+                    return SolveResult::Unsat;
+                }
+
+                continue;
+            }
+
+            if self.learned_count >= self.max_learned {
+                self.reduce_learned();
+            }
+
+            // Assumptions are decided first, one per level
+            let mut decision = None;
+
+            while self.decision_level() < assumptions.len() {
+                let lit = assumptions[self.decision_level()];
+
+                match self.value(lit) {
+                    Some(true) => self.trail_limits.push(self.trail.len()),
+                    Some(false) => {
+                        self.analyse_final(lit);
+                        return SolveResult::Unsat;
+                    }
+                    None => {
+                        decision = Some(lit);
+                        break;
+                    }
+                }
+            }
+
+            let decision = match decision.or_else(|| self.pick_branch_lit()) {
+                Some(lit) => lit,
+                None => return SolveResult::Sat(self.model()),
+            };
+
+            self.stats.decisions += 1;
+            self.trail_limits.push(self.trail.len());
+            self.enqueue(decision, None);
+        }
+    }
+
+    /// Adds `lits`, none of which are assigned, at decision level 0.
+    ///
+    /// Returns the index of the new clause if it has at least two literals.
+    fn add_clause_at_top(&mut self, lits: Vec<Lit>, learned: bool) -> Option<usize> {
+        match lits.as_slice() {
+            [] => {
+                self.ok = false;
+                None
+            }
+            [unit] => {
+                self.enqueue(*unit, None);
+
+                if self.propagate().is_some() {
+                    self.ok = false;
+                }
+
+                None
+            }
+            _ => Some(self.attach(lits, learned, 0)),
+        }
+    }
+
+    fn attach(&mut self, lits: Vec<Lit>, learned: bool, lbd: usize) -> usize {
+        let index = self.clauses.len();
+
+        self.watches[lits[0].code()].push(index);
+        self.watches[lits[1].code()].push(index);
+        self.clauses.push(ClauseData {
+            lits,
+            learned,
+            deleted: false,
+            lbd,
+            vivified: false,
+        });
+
+        if learned {
+            self.learned_count += 1;
+        }
+
+        index
+    }
+
+    /// Adds a learned clause after backjumping, and asserts its first literal.
+    fn learn(&mut self, learned: Vec<Lit>) {
+        self.stats.learned_clauses += 1;
+        self.export_clause(&learned);
+
+        if learned.len() == 1 {
+            self.enqueue(learned[0], None);
+            return;
+        }
+
+        let lbd = self.lbd(&learned);
+        let asserting = learned[0];
+        let index = self.attach(learned, true, lbd);
+        self.enqueue(asserting, Some(index));
+    }
+
+    fn ensure_var(&mut self, var: Var) {
+        let count = var.index() + 1;
+
+        if self.values.len() >= count {
+            return;
+        }
+
+        self.watches.resize(count * 2, Vec::new());
+        self.used.resize(count, false);
+        self.values.resize(count, None);
+        self.levels.resize(count, 0);
+        self.reasons.resize(count, None);
+        self.saved_phases.resize(count, false);
+        self.seen.resize(count, false);
+        self.xor_columns.resize(count, None);
+        self.cardinality_watches.resize(count * 2, Vec::new());
+
+        while self.activity.len() < count {
+            // Random initial activities give each seed a different initial order
+            let initial = if self.config.seed == 0 {
+                0.0
+            } else {
+                self.rng.random::<f64>() * 1e-5
+            };
+
+            self.activity.push(initial);
+            self.heap.insert(self.activity.len() - 1, &self.activity);
+        }
+    }
+
+    fn value(&self, lit: Lit) -> Option<bool> {
+        self.values[lit.index()].map(|value| lit.evaluate(value))
+    }
+
+    fn decision_level(&self) -> usize {
+        self.trail_limits.len()
+    }
+
+    fn enqueue(&mut self, lit: Lit, reason: Option<usize>) {
+        self.values[lit.index()] = Some(lit.polarity());
+        self.levels[lit.index()] = self.decision_level();
+        self.reasons[lit.index()] = reason;
+        self.trail.push(lit);
+
+        for index in &self.cardinality_watches[lit.code()] {
+            self.cardinalities[*index].true_count += 1;
+        }
+    }
+
+    /// Undoes every assignment above `level`.
+    fn cancel_until(&mut self, level: usize) {
+        if self.decision_level() <= level {
+            return;
+        }
+
+        let limit = self.trail_limits[level];
+
+        for lit in self.trail.drain(limit..).rev() {
+            self.values[lit.index()] = None;
+
+            for index in &self.cardinality_watches[lit.code()] {
+                self.cardinalities[*index].true_count -= 1;
+            }
+
+            // Only explanations are reasons without being attached
+            if let Some(reason) = self.reasons[lit.index()].take() {
+                if self.clauses[reason].deleted {
+                    self.clauses[reason].lits = Vec::new();
+                    self.free_explanations.push(reason);
+                }
+            }
+
+            self.saved_phases[lit.index()] = lit.polarity();
+            self.heap.insert(lit.index(), &self.activity);
+        }
+
+        self.trail_limits.truncate(level);
+        self.queue_head = self.queue_head.min(limit);
+    }
+
+    /// Propagates every enqueued literal, returning the index of a falsified clause if there is a conflict.
+    fn propagate(&mut self) -> Option<usize> {
+        while self.queue_head < self.trail.len() {
+            let false_lit = !self.trail[self.queue_head];
+            self.queue_head += 1;
+            self.stats.propagations += 1;
+
+            // Watchers are compacted in place, keeping those that still watch `false_lit`
+            let mut watchers = std::mem::take(&mut self.watches[false_lit.code()]);
+            let mut kept = 0;
+            let mut conflict = None;
+
+            for position in 0..watchers.len() {
+                let index = watchers[position];
+                let clause = &mut self.clauses[index];
+
+                if clause.deleted {
+                    continue;
+                }
+
+                if conflict.is_some() {
+                    watchers[kept] = index;
+                    kept += 1;
+                    continue;
+                }
+
+                // Keep the false literal in the second position
+                if clause.lits[0] == false_lit {
+                    clause.lits.swap(0, 1);
+                }
+
+                let first = clause.lits[0];
+                let first_value = self.values[first.index()].map(|value| first.evaluate(value));
+
+                if first_value != Some(true) {
+                    let replacement = clause.lits[2..].iter().position(|lit| {
+                        self.values[lit.index()].map(|value| lit.evaluate(value)) != Some(false)
+                    });
+
+                    if let Some(offset) = replacement {
+                        clause.lits.swap(1, offset + 2);
+                        self.watches[clause.lits[1].code()].push(index);
+                        continue;
+                    }
+
+                    match first_value {
+                        Some(false) => conflict = Some(index),
+                        _ => self.enqueue(first, Some(index)),
+                    }
+                }
+
+                watchers[kept] = index;
+                kept += 1;
+            }
+
+            watchers.truncate(kept);
+            self.watches[false_lit.code()] = watchers;
+
+            if conflict.is_none() && !self.cardinality_watches[(!false_lit).code()].is_empty() {
+                conflict = self.propagate_cardinalities(!false_lit);
+            }
+
+            if conflict.is_some() {
+                self.queue_head = self.trail.len();
+                return conflict;
+            }
+        }
+
+        None
+    }
+
+    /// Propagates the cardinality constraints containing `lit`, which has just become true, returning an explanation of any conflict.
+    ///
+    /// A constraint with more true literals than allowed is a conflict, and one with exactly as many makes its other literals false.
+    fn propagate_cardinalities(&mut self, lit: Lit) -> Option<usize> {
+        let watching = std::mem::take(&mut self.cardinality_watches[lit.code()]);
+        let mut conflict = None;
+
+        for index in &watching {
+            let cardinality = &self.cardinalities[*index];
+
+            if cardinality.true_count < cardinality.bound {
+                continue;
+            }
+
+            // The complements of the true literals, starting with `lit`
+            let mut complements = vec![!lit];
+            complements.extend(
+                cardinality
+                    .lits
+                    .iter()
+                    .filter(|other| **other != lit && self.value(**other) == Some(true))
+                    .map(|other| !*other),
+            );
+
+            if cardinality.true_count > cardinality.bound {
+                complements.truncate(cardinality.bound + 1);
+
+                self.stats.cardinality_conflicts += 1;
+                conflict = Some(self.explain(complements));
+                break;
+            }
+
+            let unassigned: Vec<Lit> = cardinality
+                .lits
+                .iter()
+                .copied()
+                .filter(|other| self.value(*other).is_none())
+                .collect();
+
+            for other in unassigned {
+                self.stats.cardinality_propagations += 1;
+
+                if self.decision_level() == 0 {
+                    self.enqueue(!other, None);
+                } else {
+                    let mut lits = vec![!other];
+                    lits.extend(&complements);
+
+                    let reason = self.explain(lits);
+                    self.enqueue(!other, Some(reason));
+                }
+            }
+        }
+
+        self.cardinality_watches[lit.code()] = watching;
+
+        conflict
+    }
+
+    /// Propagates the clauses and the parity constraints until neither assigns anything more.
+    fn propagate_all(&mut self) -> Option<usize> {
+        loop {
+            if let Some(conflict) = self.propagate() {
+                return Some(conflict);
+            }
+
+            let assigned = self.trail.len();
+
+            if let Some(conflict) = self.propagate_xors() {
+                self.stats.xor_conflicts += 1;
+                return Some(conflict);
+            }
+
+            if self.trail.len() == assigned {
+                return None;
+            }
+        }
+    }
+
+    /// Propagates the parity constraints by Gauss-Jordan elimination on the unassigned variables, returning an explanation of any conflict.
+    ///
+    /// A row whose pivot is assigned is pivoted onto one of its unassigned variables, if it has any. Each row with unassigned variables then has one of its own,
+    /// so no sum of rows has fewer unassigned variables than its parts: rows with none are conflicts if violated, and rows with one fix its value.
+    /// As this finds every implied literal as soon as it is implied, every explanation has a literal from the current decision level.
+    fn propagate_xors(&mut self) -> Option<usize> {
+        if self.xor_rows.is_empty() {
+            return None;
+        }
+
+        let words = self.xor_vars.len().div_ceil(64);
+        let mut unassigned = vec![0u64; words];
+        let mut true_columns = vec![0u64; words];
+
+        for (column, var) in self.xor_vars.iter().enumerate() {
+            match self.values[*var] {
+                None => unassigned[column / 64] |= 1 << (column % 64),
+                Some(true) => true_columns[column / 64] |= 1 << (column % 64),
+                Some(false) => {}
+            }
+        }
+
+        for row in 0..self.xor_rows.len() {
+            let current = self.xor_pivots[row];
+
+            if unassigned[current / 64] >> (current % 64) & 1 == 1 {
+                continue;
+            }
+
+            let bits = &self.xor_rows[row].0;
+
+            // Pivot columns are set in only one row, so an unassigned column of this row is not another row's pivot
+            if let Some(column) = columns(&and(bits, &unassigned)).next() {
+                pivot(&mut self.xor_rows, row, column);
+                self.xor_pivots[row] = column;
+            }
+        }
+
+        for row in 0..self.xor_rows.len() {
+            let (bits, parity) = &self.xor_rows[row];
+            let free = count_ones(&and(bits, &unassigned));
+
+            if free > 1 {
+                continue;
+            }
+
+            let vars: Vec<usize> = columns(bits).map(|column| self.xor_vars[column]).collect();
+            let value = *parity ^ (count_ones(&and(bits, &true_columns)) % 2 == 1);
+
+            // The literals of the row made false by the current assignment
+            let falsified = |solver: &Solver| -> Vec<Lit> {
+                vars.iter()
+                    .filter_map(|var| {
+                        solver.values[*var].map(|value| Lit::from_index(*var, !value).unwrap())
+                    })
+                    .collect()
+            };
+
+            if free == 0 {
+                if value {
+                    return Some(self.explain(falsified(self)));
+                }
+
+                continue;
+            }
+
+            // The only unassigned column is the pivot, which is in no other row, so later rows are unaffected
+            let lit = Lit::from_index(self.xor_vars[self.xor_pivots[row]], value).unwrap();
+            self.stats.xor_propagations += 1;
+
+            if self.decision_level() == 0 {
+                self.enqueue(lit, None);
+            } else {
+                let mut lits = vec![lit];
+                lits.extend(falsified(self));
+
+                let reason = self.explain(lits);
+                self.enqueue(lit, Some(reason));
+            }
+        }
+
+        None
+    }
+
+    /// Stores an explanation clause, which is only read by conflict analysis and so is never attached.
+    fn explain(&mut self, lits: Vec<Lit>) -> usize {
+        let clause = ClauseData {
+            lits,
+            learned: true,
+            deleted: true,
+            lbd: 0,
+            vivified: true,
+        };
+
+        match self.free_explanations.pop() {
+            Some(index) => {
+                self.clauses[index] = clause;
+                index
+            }
+            None => {
+                self.clauses.push(clause);
+                self.clauses.len() - 1
+            }
+        }
+    }
+
+    /// Frees the slot of a conflict once it is no longer needed, if it is an explanation.
+    fn release_conflict(&mut self, index: usize) {
+        if self.clauses[index].deleted {
+            self.clauses[index].lits = Vec::new();
+            self.free_explanations.push(index);
+        }
+    }
+
+    /// Derives a first UIP clause from a conflict, returning it with the level to backjump to.
+    ///
+    /// The first literal of the clause is asserting, and the second has the backjump level.
+    fn analyse(&mut self, conflict: usize) -> (Vec<Lit>, usize) {
+        let mut learned = vec![Lit::from_index(0, false).unwrap()];
+        let mut pending = 0;
+        let mut index = self.trail.len();
+        let mut clause = conflict;
+        let mut implied: Option<Lit> = None;
+
+        loop {
+            let skip = usize::from(implied.is_some());
+
+            for position in skip..self.clauses[clause].lits.len() {
+                let lit = self.clauses[clause].lits[position];
+                let var = lit.index();
+
+                if self.seen[var] || self.levels[var] == 0 {
+                    continue;
+                }
+
+                self.seen[var] = true;
+                self.bump(var);
+
+                if self.levels[var] == self.decision_level() {
+                    pending += 1;
+                } else {
+                    learned.push(lit);
+                }
+            }
+
+            // Find the next literal on the trail involved in the conflict
+            loop {
+                index -= 1;
+
+                if self.seen[self.trail[index].index()] {
+                    break;
+                }
+            }
+
+            let lit = self.trail[index];
+            self.seen[lit.index()] = false;
+            implied = Some(lit);
+            pending -= 1;
+
+            if pending == 0 {
+                break;
+            }
+
+            clause = self.reasons[lit.index()].unwrap();
+        }
+
+        learned[0] = !implied.unwrap();
+
+        // Drop literals implied by the rest of the clause
+        let candidates = learned.split_off(1);
+        let mut minimised = learned;
+
+        for lit in &candidates {
+            let redundant = self.reasons[lit.index()].is_some_and(|reason| {
+                self.clauses[reason].lits[1..]
+                    .iter()
+                    .all(|other| self.seen[other.index()] || self.levels[other.index()] == 0)
+            });
+
+            if !redundant {
+                minimised.push(*lit);
+            }
+        }
+
+        for lit in &candidates {
+            self.seen[lit.index()] = false;
+        }
+
+        // Move the literal with the highest level after the asserting literal
+        let mut level = 0;
+
+        if minimised.len() > 1 {
+            let highest = (1..minimised.len())
+                .max_by_key(|i| self.levels[minimised[*i].index()])
+                .unwrap();
+
+            minimised.swap(1, highest);
+            level = self.levels[minimised[1].index()];
+        }
+
+        (minimised, level)
+    }
+
+    /// Finds the assumptions responsible for `lit`, an assumption, being false.
+    fn analyse_final(&mut self, lit: Lit) {
+        self.failed_assumptions = vec![lit];
+
+        if self.decision_level() == 0 {
+            return;
+        }
+
+        self.seen[lit.index()] = true;
+
+        for i in (self.trail_limits[0]..self.trail.len()).rev() {
+            let trail_lit = self.trail[i];
+            let var = trail_lit.index();
+
+            if !self.seen[var] {
+                continue;
+            }
+
+            match self.reasons[var] {
+                // Decisions below the assumption count are assumptions
+                None => self.failed_assumptions.push(trail_lit),
+                Some(reason) => {
+                    for other in &self.clauses[reason].lits[1..] {
+                        if self.levels[other.index()] > 0 {
+                            self.seen[other.index()] = true;
+                        }
+                    }
+                }
+            }
+
+            self.seen[var] = false;
+        }
+
+        self.seen[lit.index()] = false;
+    }
+
+    fn lbd(&self, lits: &[Lit]) -> usize {
+        let mut levels: Vec<usize> = lits.iter().map(|lit| self.levels[lit.index()]).collect();
+        levels.sort();
+        levels.dedup();
+
+        levels.len()
+    }
+
+    fn bump(&mut self, var: usize) {
+        self.activity[var] += self.activity_increment;
+
+        if self.activity[var] > 1e100 {
+            for activity in &mut self.activity {
+                *activity *= 1e-100;
+            }
+
+            self.activity_increment *= 1e-100;
+        }
+
+        self.heap.increase(var, &self.activity);
+    }
+
+    fn pick_branch_lit(&mut self) -> Option<Lit> {
+        let mut next = None;
+
+        if self.config.random_var_freq > 0.0
+            && !self.heap.is_empty()
+            && self.rng.random_bool(self.config.random_var_freq)
+        {
+            let var = self.heap.vars[self.rng.random_range(0..self.heap.vars.len())];
+
+            if self.values[var].is_none() && self.used[var] {
+                next = Some(var);
+            }
+        }
+
+        while next.is_none() {
+            let var = self.heap.pop(&self.activity)?;
+
+            if self.values[var].is_none() && self.used[var] {
+                next = Some(var);
+            }
+        }
+
+        let var = next.unwrap();
+        let polarity = match self.config.polarity {
+            Polarity::Saved => self.saved_phases[var],
+            Polarity::Negative => false,
+            Polarity::Random => self.rng.random_bool(0.5),
+        };
+
+        Some(Lit::from_index(var, polarity).unwrap())
+    }
+
+    /// The number of conflicts before the next restart, or `None` to never restart.
+    fn restart_limit(&self) -> Option<u64> {
+        match self.config.restarts {
+            Restarts::Never => None,
+            Restarts::Luby { unit } => Some(unit * luby(self.restart_count)),
+            Restarts::Geometric { first, factor } => {
+                Some((first as f64 * factor.powi(self.restart_count as i32)) as u64)
+            }
+        }
+    }
+
+    /// Deletes the less useful half of the learned clauses that are not reasons.
+    fn reduce_learned(&mut self) {
+        let mut candidates: Vec<usize> = (0..self.clauses.len())
+            .filter(|index| {
+                let clause = &self.clauses[*index];
+
+                clause.learned
+                    && !clause.deleted
+                    && clause.lits.len() > 2
+                    && !self.is_reason(*index)
+            })
+            .collect();
+
+        candidates.sort_by_key(|index| std::cmp::Reverse(self.clauses[*index].lbd));
+
+        for index in &candidates[..candidates.len() / 2] {
+            let clause = &mut self.clauses[*index];
+            clause.deleted = true;
+            clause.lits = Vec::new();
+
+            self.learned_count -= 1;
+            self.stats.deleted_clauses += 1;
+        }
+
+        self.max_learned += self.max_learned / 10;
+    }
+
+    fn is_reason(&self, index: usize) -> bool {
+        let first = self.clauses[index].lits[0];
+
+        self.value(first) == Some(true) && self.reasons[first.index()] == Some(index)
+    }
+
+    fn model(&self) -> Assignment {
+        let mut assignment = Assignment::new();
+
+        for (index, value) in self.values.iter().enumerate() {
+            if let Some(value) = value {
+                assignment.set(Var::from_index(index).unwrap(), *value);
+            }
+        }
+
+        assignment
+    }
+
+    /// Simplifies the clauses between restarts. Must be called at decision level 0 with nothing left to propagate.
+    ///
+    /// Returns `false` if the clauses are found to be unsatisfiable.
+    fn inprocess(&mut self) -> bool {
+        let searched = self.stats.propagations - self.inprocess_propagations;
+        let budget =
+            MIN_INPROCESS_EFFORT.max((searched as f64 * self.config.inprocess_effort) as u64);
+
+        // Probing and vivification assign literals only temporarily, so should not change the saved phases
+        let phases = self.saved_phases.clone();
+
+        self.probe(budget);
+
+        if self.ok {
+            self.remove_satisfied();
+            self.subsume();
+        }
+
+        if self.ok {
+            self.vivify(budget);
+        }
+
+        self.saved_phases = phases;
+        self.inprocess_propagations = self.stats.propagations;
+
+        self.ok
+    }
+
+    /// Assigns literals in turn and propagates, assigning the complement of any that lead to a conflict.
+    ///
+    /// Literals implied through longer clauses are learned as binary clauses with the probe, which is hyper-binary resolution.
+    fn probe(&mut self, budget: u64) {
+        let start = self.stats.propagations;
+        let count = self.values.len();
+
+        for _ in 0..count {
+            if self.stats.propagations - start >= budget {
+                break;
+            }
+
+            let var = self.probe_cursor;
+            self.probe_cursor = (self.probe_cursor + 1) % count;
+
+            if !self.used[var] {
+                continue;
+            }
+
+            for polarity in [true, false] {
+                let lit = Lit::from_index(var, polarity).unwrap();
+
+                // Either polarity may already have failed
+                if self.value(lit).is_some() {
+                    break;
+                }
+
+                self.probe_lit(lit);
+
+                if !self.ok {
+                    return;
+                }
+            }
+        }
+    }
+
+    fn probe_lit(&mut self, lit: Lit) {
+        self.trail_limits.push(self.trail.len());
+        self.enqueue(lit, None);
+
+        if let Some(conflict) = self.propagate() {
+            self.release_conflict(conflict);
+            self.cancel_until(0);
+            self.stats.failed_literals += 1;
+            self.enqueue(!lit, None);
+
+            if self.propagate().is_some() {
+                self.ok = false;
+            }
+
+            return;
+        }
+
+        // A literal is implied by the probe alone, so resolving its reason with the implications of the probe gives a binary clause
+        let mut resolvents = Vec::new();
+
+        for position in self.trail_limits[0] + 1..self.trail.len() {
+            let implied = self.trail[position];
+            let Some(reason) = self.reasons[implied.index()] else {
+                continue;
+            };
+
+            let probed = self.clauses[reason].lits[1..]
+                .iter()
+                .filter(|other| self.levels[other.index()] > 0)
+                .count();
+
+            if probed >= 2 {
+                resolvents.push(vec![!lit, implied]);
+            }
+        }
+
+        self.cancel_until(0);
+
+        for resolvent in resolvents {
+            self.stats.hyper_binary_resolvents += 1;
+            self.attach(resolvent, true, 2);
+        }
+    }
+
+    /// Shortens clauses by assigning their literals false in turn, keeping only those needed to reach a conflict or a true literal.
+    ///
+    /// Learned clauses with the lowest LBD are tried first, then irredundant clauses not yet tried.
+    fn vivify(&mut self, budget: u64) {
+        let candidates = |clauses: &[ClauseData], learned: bool| -> Vec<usize> {
+            (0..clauses.len())
+                .filter(|index| {
+                    let clause = &clauses[*index];
+
+                    clause.learned == learned
+                        && !clause.deleted
+                        && !clause.vivified
+                        && clause.lits.len() > 2
+                })
+                .collect()
+        };
+
+        let mut learned = candidates(&self.clauses, true);
+        learned.sort_by_key(|index| self.clauses[*index].lbd);
+
+        let mut irredundant = candidates(&self.clauses, false);
+
+        if irredundant.is_empty() {
+            // Every irredundant clause has been tried, so start again
+            for clause in &mut self.clauses {
+                if !clause.learned {
+                    clause.vivified = false;
+                }
+            }
+
+            irredundant = candidates(&self.clauses, false);
+        }
+
+        // Clauses are changed afterwards, as changing them would invalidate the watches
+        let mut shortened = Vec::new();
+
+        for indices in [learned, irredundant] {
+            let start = self.stats.propagations;
+
+            for index in indices {
+                if self.stats.propagations - start >= budget / 2 {
+                    break;
+                }
+
+                self.clauses[index].vivified = true;
+
+                if let Some(lits) = self.vivify_clause(index) {
+                    shortened.push((index, lits));
+                }
+            }
+        }
+
+        if shortened.is_empty() {
+            return;
+        }
+
+        let mut units = Vec::new();
+
+        for (index, lits) in shortened {
+            self.stats.vivified_clauses += 1;
+            self.stats.vivified_literals += (self.clauses[index].lits.len() - lits.len()) as u64;
+
+            if lits.len() == 1 {
+                units.push(lits[0]);
+                self.delete(index);
+            } else {
+                self.clauses[index].lits = lits;
+            }
+        }
+
+        self.rebuild_watches();
+
+        // Watched literals may be false at the top level, so everything is propagated again
+        self.queue_head = 0;
+
+        for lit in units {
+            match self.value(lit) {
+                Some(true) => {}
+                Some(false) => self.ok = false,
+                None => self.enqueue(lit, None),
+            }
+        }
+
+        if self.ok && self.propagate().is_some() {
+            self.ok = false;
+        }
+    }
+
+    /// A subset of a clause implied by the clauses, if it is shorter.
+    ///
+    /// The clause itself takes part in propagation, but the subset still replaces it: the subset is implied, and implies the clause.
+    fn vivify_clause(&mut self, index: usize) -> Option<Vec<Lit>> {
+        let lits = self.clauses[index].lits.clone();
+
+        if lits.iter().any(|lit| self.value(*lit).is_some()) {
+            return None;
+        }
+
+        let mut kept = Vec::new();
+        self.trail_limits.push(self.trail.len());
+
+        for lit in &lits {
+            match self.value(*lit) {
+                Some(true) => {
+                    kept.push(*lit);
+                    break;
+                }
+                Some(false) => {}
+                None => {
+                    kept.push(*lit);
+                    self.enqueue(!*lit, None);
+
+                    if let Some(conflict) = self.propagate() {
+                        self.release_conflict(conflict);
+                        break;
+                    }
+                }
+            }
+        }
+
+        self.cancel_until(0);
+
+        (kept.len() < lits.len()).then_some(kept)
+    }
+
+    /// Deletes clauses satisfied at the top level and removes false literals from the rest.
+    fn remove_satisfied(&mut self) {
+        for index in 0..self.clauses.len() {
+            if self.clauses[index].deleted {
+                continue;
+            }
+
+            if self.clauses[index]
+                .lits
+                .iter()
+                .any(|lit| self.value(*lit) == Some(true))
+            {
+                self.delete(index);
+                continue;
+            }
+
+            let values = &self.values;
+            self.clauses[index]
+                .lits
+                .retain(|lit| values[lit.index()].is_none());
+        }
+
+        // Top level assignments are never analysed, so their reasons can be forgotten
+        for lit in &self.trail {
+            self.reasons[lit.index()] = None;
+        }
+
+        self.rebuild_watches();
+    }
+
+    /// Removes irredundant clauses subsumed by others, and strengthens them by self-subsuming resolution.
+    fn subsume(&mut self) {
+        let mut db = ClauseDb::default();
+        let mut indices = Vec::new();
+
+        for (index, clause) in self.clauses.iter().enumerate() {
+            if !clause.learned && !clause.deleted {
+                // Clauses are duplicate free and not tautologies, so always added
+                db.add(clause.lits.clone());
+                indices.push(index);
+            }
+        }
+
+        let before = self.stats.subsumption;
+        subsume_all(&mut db, &mut self.stats.subsumption);
+
+        if self.stats.subsumption == before {
+            return;
+        }
+
+        let mut units = Vec::new();
+
+        for (db_index, index) in indices.into_iter().enumerate() {
+            match db.get(db_index) {
+                None => self.delete(index),
+                Some(lits) if lits.len() < 2 => {
+                    units.push(lits.clone());
+                    self.delete(index);
+                }
+                Some(lits) => self.clauses[index].lits = lits.clone(),
+            }
+        }
+
+        self.rebuild_watches();
+
+        for lits in units {
+            if !self.ok {
+                break;
+            }
+
+            match lits.first() {
+                Some(lit) if self.value(*lit) == Some(true) => {}
+                Some(lit) if self.value(*lit) == Some(false) => self.ok = false,
+                _ => {
+                    self.add_clause_at_top(lits, false);
+                }
+            }
+        }
+    }
+
+    fn delete(&mut self, index: usize) {
+        let clause = &mut self.clauses[index];
+        clause.deleted = true;
+        clause.lits = Vec::new();
+
+        if clause.learned {
+            self.learned_count -= 1;
+        }
+    }
+
+    fn rebuild_watches(&mut self) {
+        for watches in &mut self.watches {
+            watches.clear();
+        }
+
+        for (index, clause) in self.clauses.iter().enumerate() {
+            if !clause.deleted {
+                self.watches[clause.lits[0].code()].push(index);
+                self.watches[clause.lits[1].code()].push(index);
+            }
+        }
+    }
+
+    /// Connects this solver to an exchange, exporting learned clauses of at most `max_len` literals.
+    pub(crate) fn share_clauses(
+        &mut self,
+        exchange: Arc<ClauseExchange>,
+        id: usize,
+        max_len: usize,
+    ) {
+        self.exchange = Some(ExchangeLink {
+            exchange,
+            id,
+            max_len,
+            cursor: 0,
+        });
+    }
+
+    fn export_clause(&mut self, lits: &[Lit]) {
+        if let Some(link) = &self.exchange {
+            if lits.len() <= link.max_len {
+                link.exchange
+                    .clauses
+                    .lock()
+                    .unwrap()
+                    .push((link.id, lits.to_vec()));
+            }
+        }
+    }
+
+    /// Adds clauses exported by other solvers since the last import. Must be called at decision level 0.
+    ///
+    /// Returns `false` if an imported clause makes the formula unsatisfiable.
+    fn import_clauses(&mut self) -> bool {
+        let Some(link) = &mut self.exchange else {
+            return true;
+        };
+
+        let imported: Vec<Vec<Lit>> = {
+            let clauses = link.exchange.clauses.lock().unwrap();
+            let new = clauses[link.cursor..]
+                .iter()
+                .filter(|(id, _)| *id != link.id)
+                .map(|(_, lits)| lits.clone())
+                .collect();
+            link.cursor = clauses.len();
+
+            new
+        };
+
+        for mut lits in imported {
+            if lits.iter().any(|lit| self.value(*lit) == Some(true)) {
+                continue;
+            }
+
+            lits.retain(|lit| self.value(*lit).is_none());
+            self.stats.imported_clauses += 1;
+            self.add_clause_at_top(lits, true);
+
+            if !self.ok {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// Gauss-Jordan elimination of `rows` on each of `columns` in turn, returning the number of rows with a pivot, which come first.
+fn eliminate(rows: &mut [(Vec<u64>, bool)], columns: impl IntoIterator<Item = usize>) -> usize {
+    let mut rank = 0;
+
+    for column in columns {
+        let (word, bit) = (column / 64, 1 << (column % 64));
+
+        let Some(found) = (rank..rows.len()).find(|row| rows[*row].0[word] & bit != 0) else {
+            continue;
+        };
+
+        rows.swap(rank, found);
+        pivot(rows, rank, column);
+        rank += 1;
+    }
+
+    rank
+}
+
+/// Adds row `pivot_row` to every other row with `column` set, so that only it has `column` set.
+fn pivot(rows: &mut [(Vec<u64>, bool)], pivot_row: usize, column: usize) {
+    let (word, bit) = (column / 64, 1 << (column % 64));
+    let (pivot, pivot_parity) = rows[pivot_row].clone();
+
+    for (index, (bits, parity)) in rows.iter_mut().enumerate() {
+        if index != pivot_row && bits[word] & bit != 0 {
+            for (a, b) in bits.iter_mut().zip(&pivot) {
+                *a ^= b;
+            }
+
+            *parity ^= pivot_parity;
+        }
+    }
+}
+
+/// The columns set in both rows of bits.
+fn and(a: &[u64], b: &[u64]) -> Vec<u64> {
+    a.iter().zip(b).map(|(a, b)| a & b).collect()
+}
+
+fn count_ones(bits: &[u64]) -> u32 {
+    bits.iter().map(|word| word.count_ones()).sum()
+}
+
+/// The columns set in a row of bits.
+fn columns(bits: &[u64]) -> impl Iterator<Item = usize> + '_ {
+    bits.iter().enumerate().flat_map(|(word, bits)| {
+        (0..64)
+            .filter(move |bit| bits >> bit & 1 == 1)
+            .map(move |bit| word * 64 + bit)
+    })
+}
+
+/// The fewest propagations each inprocessing technique may use in a round.
+const MIN_INPROCESS_EFFORT: u64 = 10_000;
+
+/// The `i`th term (from 0) of the Luby sequence.
+fn luby(mut i: u64) -> u64 {
+    // Find the complete subsequence containing i, then its position within it
+    let mut size = 1;
+    let mut power = 0;
+
+    while size < i + 1 {
+        power += 1;
+        size = 2 * size + 1;
+    }
+
+    while size - 1 != i {
+        size = (size - 1) / 2;
+        power -= 1;
+        i %= size;
+    }
+
+    1 << power
+}
+
+/// A max-heap of variables ordered by activity.
+#[derive(Debug, Default)]
+struct VarHeap {
+    vars: Vec<usize>,
+    /// The position of each variable in `vars`, if present.
+    positions: Vec<Option<usize>>,
+}
+
+impl VarHeap {
+    fn is_empty(&self) -> bool {
+        self.vars.is_empty()
+    }
+
+    fn insert(&mut self, var: usize, activity: &[f64]) {
+        if self.positions.len() <= var {
+            self.positions.resize(var + 1, None);
+        }
+
+        if self.positions[var].is_some() {
+            return;
+        }
+
+        self.positions[var] = Some(self.vars.len());
+        self.vars.push(var);
+        self.sift_up(self.vars.len() - 1, activity);
+    }
+
+    /// Restores the heap order after `var`'s activity increased.
+    fn increase(&mut self, var: usize, activity: &[f64]) {
+        if let Some(position) = self.positions.get(var).copied().flatten() {
+            self.sift_up(position, activity);
+        }
+    }
+
+    fn pop(&mut self, activity: &[f64]) -> Option<usize> {
+        let top = *self.vars.first()?;
+        let last = self.vars.pop().unwrap();
+        self.positions[top] = None;
+
+        if !self.vars.is_empty() {
+            self.vars[0] = last;
+            self.positions[last] = Some(0);
+            self.sift_down(0, activity);
+        }
+
+        Some(top)
+    }
+
+    fn sift_up(&mut self, mut position: usize, activity: &[f64]) {
+        while position > 0 {
+            let parent = (position - 1) / 2;
+
+            if activity[self.vars[parent]] >= activity[self.vars[position]] {
+                break;
+            }
+
+            self.swap(position, parent);
+            position = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut position: usize, activity: &[f64]) {
+        loop {
+            let mut largest = position;
+
+            for child in [2 * position + 1, 2 * position + 2] {
+                if child < self.vars.len()
+                    && activity[self.vars[child]] > activity[self.vars[largest]]
+                {
+                    largest = child;
+                }
+            }
+
+            if largest == position {
+                break;
+            }
+
+            self.swap(position, largest);
+            position = largest;
+        }
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.vars.swap(a, b);
+        self.positions[self.vars[a]] = Some(a);
+        self.positions[self.vars[b]] = Some(b);
+    }
+}
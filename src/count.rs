@@ -0,0 +1,411 @@
+//! Exact model counting (#SAT)
+//!
+//! The counter is a DPLL search that propagates units, splits the remaining clauses into variable-disjoint components and counts each component separately.
+//! Component counts are cached, so a component reached by several branches is only counted once.
+
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    fmt, ops,
+};
+
+use crate::{formula::clausal_beside, solve_with_assumptions, Formula, Lit, Var};
+
+/// An arbitrary-precision, non-negative model count.
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct Count {
+    /// Little-endian base 2^32 digits, with no trailing zeros.
+    limbs: Vec<u32>,
+}
+
+impl Count {
+    /// A count of zero.
+    pub fn zero() -> Count {
+        Count::default()
+    }
+
+    /// A count of one.
+    pub fn one() -> Count {
+        Count::from(1)
+    }
+
+    /// Two raised to the power `exponent`.
+    pub fn pow2(exponent: usize) -> Count {
+        let mut limbs = vec![0; exponent / 32];
+        limbs.push(1 << (exponent % 32));
+
+        Count { limbs }
+    }
+
+    /// Checks if this count is zero.
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    /// Converts this count to a `u64` if it fits.
+    pub fn to_u64(&self) -> Option<u64> {
+        match self.limbs.as_slice() {
+            [] => Some(0),
+            [low] => Some(*low as u64),
+            [low, high] => Some((*high as u64) << 32 | *low as u64),
+            _ => None,
+        }
+    }
+
+    /// Converts this count to the nearest `f64`, which may be infinite.
+    pub fn to_f64(&self) -> f64 {
+        self.limbs
+            .iter()
+            .rev()
+            .fold(0.0, |acc, limb| acc * 4294967296.0 + *limb as f64)
+    }
+
+    /// Divides by a small divisor in place, returning the remainder.
+    fn div_rem_small(&mut self, divisor: u32) -> u32 {
+        let mut remainder = 0u64;
+
+        for limb in self.limbs.iter_mut().rev() {
+            let value = remainder << 32 | *limb as u64;
+            *limb = (value / divisor as u64) as u32;
+            remainder = value % divisor as u64;
+        }
+
+        self.normalise();
+        remainder as u32
+    }
+
+    fn normalise(&mut self) {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+    }
+}
+
+impl From<u64> for Count {
+    fn from(value: u64) -> Count {
+        let mut count = Count {
+            limbs: vec![value as u32, (value >> 32) as u32],
+        };
+        count.normalise();
+
+        count
+    }
+}
+
+impl ops::Add for &Count {
+    type Output = Count;
+
+    fn add(self, other: &Count) -> Count {
+        let mut limbs = Vec::with_capacity(self.limbs.len().max(other.limbs.len()) + 1);
+        let mut carry = 0u64;
+
+        for i in 0..self.limbs.len().max(other.limbs.len()) {
+            let sum = *self.limbs.get(i).unwrap_or(&0) as u64
+                + *other.limbs.get(i).unwrap_or(&0) as u64
+                + carry;
+            limbs.push(sum as u32);
+            carry = sum >> 32;
+        }
+
+        limbs.push(carry as u32);
+
+        let mut count = Count { limbs };
+        count.normalise();
+
+        count
+    }
+}
+
+impl ops::Add for Count {
+    type Output = Count;
+
+    fn add(self, other: Count) -> Count {
+        &self + &other
+    }
+}
+
+impl ops::Mul for &Count {
+    type Output = Count;
+
+    fn mul(self, other: &Count) -> Count {
+        let mut limbs = vec![0u32; self.limbs.len() + other.limbs.len()];
+
+        for (i, a) in self.limbs.iter().enumerate() {
+            let mut carry = 0u64;
+
+            for (j, b) in other.limbs.iter().enumerate() {
+                let product = *a as u64 * *b as u64 + limbs[i + j] as u64 + carry;
+                limbs[i + j] = product as u32;
+                carry = product >> 32;
+            }
+
+            limbs[i + other.limbs.len()] = carry as u32;
+        }
+
+        let mut count = Count { limbs };
+        count.normalise();
+
+        count
+    }
+}
+
+impl ops::Mul for Count {
+    type Output = Count;
+
+    fn mul(self, other: Count) -> Count {
+        &self * &other
+    }
+}
+
+impl Ord for Count {
+    fn cmp(&self, other: &Count) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for Count {
+    fn partial_cmp(&self, other: &Count) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Gives decimal encoding
+impl fmt::Debug for Count {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+
+        let mut value = self.clone();
+        let mut chunks = Vec::new();
+
+        while !value.is_zero() {
+            chunks.push(value.div_rem_small(1_000_000_000));
+        }
+
+        write!(f, "{}", chunks.pop().unwrap())?;
+
+        for chunk in chunks.iter().rev() {
+            write!(f, "{:09}", chunk)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Uses Debug output
+impl fmt::Display for Count {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Counts the assignments to the variables of `formula` that satisfy it.
+pub fn count_models(formula: &Formula) -> Count {
+    count_projected_models(formula, &formula.vars())
+}
+
+/// Counts the distinct assignments to `vars` that extend to a model of `formula`.
+///
+/// Variables in `vars` that do not occur in the formula double the count.
+pub fn count_projected_models(formula: &Formula, vars: &[Var]) -> Count {
+    let (formula, _) = clausal_beside(formula, vars.iter().copied());
+    let formula = &*formula;
+
+    if formula.clauses().iter().any(|clause| clause.is_empty()) {
+        return Count::zero();
+    }
+
+    let clauses: Vec<Vec<Lit>> = formula
+        .clauses()
+        .iter()
+        .map(|clause| normalise_clause(clause.literals()))
+        .filter(|lits| !is_tautology(lits))
+        .collect();
+
+    let mut universe: HashSet<Var> = formula.vars().into_iter().collect();
+    universe.extend(vars);
+
+    let mut counter = Counter {
+        projection: vars.iter().copied().collect(),
+        cache: HashMap::new(),
+    };
+
+    counter.count(clauses, universe)
+}
+
+/// The state of a counting search.
+struct Counter {
+    /// The variables being counted over.
+    projection: HashSet<Var>,
+    /// Known counts of components, keyed by their sorted clauses.
+    cache: HashMap<Vec<Vec<Lit>>, Count>,
+}
+
+impl Counter {
+    /// Counts assignments to the projection variables in `universe` that satisfy `clauses`.
+    fn count(&mut self, mut clauses: Vec<Vec<Lit>>, mut universe: HashSet<Var>) -> Count {
+        // Propagate units, removing their variables from the universe
+        while let Some(unit) = clauses
+            .iter()
+            .find(|lits| lits.len() == 1)
+            .map(|lits| lits[0])
+        {
+            universe.remove(&unit.var());
+
+            match condition(&clauses, unit) {
+                Some(conditioned) => clauses = conditioned,
+                None => return Count::zero(),
+            }
+        }
+
+        // Variables no longer constrained by any clause are free
+        let constrained: HashSet<Var> = clauses.iter().flatten().map(|lit| lit.var()).collect();
+        let free = universe
+            .iter()
+            .filter(|var| !constrained.contains(var) && self.projection.contains(var))
+            .count();
+
+        let mut count = Count::pow2(free);
+
+        for component in components(clauses) {
+            count = &count * &self.count_component(component);
+
+            if count.is_zero() {
+                break;
+            }
+        }
+
+        count
+    }
+
+    /// Counts a connected component, whose universe is exactly the variables in its clauses.
+    fn count_component(&mut self, mut clauses: Vec<Vec<Lit>>) -> Count {
+        clauses.sort();
+
+        if let Some(count) = self.cache.get(&clauses) {
+            return count.clone();
+        }
+
+        let universe: HashSet<Var> = clauses.iter().flatten().map(|lit| lit.var()).collect();
+
+        // Branch on the most frequent projection variable
+        let mut occurrences: HashMap<Var, usize> = HashMap::new();
+
+        for lit in clauses.iter().flatten() {
+            if self.projection.contains(&lit.var()) {
+                *occurrences.entry(lit.var()).or_default() += 1;
+            }
+        }
+
+        let count = match occurrences.into_iter().max_by_key(|(var, n)| (*n, *var)) {
+            Some((var, _)) => {
+                let mut count = Count::zero();
+
+                for lit in [var.positive(), var.negative()] {
+                    if let Some(conditioned) = condition(&clauses, lit) {
+                        let mut universe = universe.clone();
+                        universe.remove(&var);
+
+                        count = &count + &self.count(conditioned, universe);
+                    }
+                }
+
+                count
+            }
+
+            // Without projection variables, all that matters is whether the component is satisfiable
+            None => {
+                let mut formula = Formula::new();
+
+                for lits in &clauses {
+                    formula.add_clause(lits.clone().into());
+                }
+
+                match solve_with_assumptions(&formula, &[]) {
+                    Some(_) => Count::one(),
+                    None => Count::zero(),
+                }
+            }
+        };
+
+        self.cache.insert(clauses, count.clone());
+
+        count
+    }
+}
+
+/// Sorts and removes duplicate literals from a clause.
+fn normalise_clause(mut lits: Vec<Lit>) -> Vec<Lit> {
+    lits.sort();
+    lits.dedup();
+
+    lits
+}
+
+/// Checks if a sorted clause contains a literal and its complement.
+fn is_tautology(lits: &[Lit]) -> bool {
+    lits.windows(2).any(|pair| pair[0] == !pair[1])
+}
+
+/// Simplifies `clauses` under the assumption that `lit` is true.
+///
+/// Returns `None` if a clause becomes empty.
+fn condition(clauses: &[Vec<Lit>], lit: Lit) -> Option<Vec<Vec<Lit>>> {
+    let mut conditioned = Vec::with_capacity(clauses.len());
+
+    for lits in clauses {
+        if lits.contains(&lit) {
+            continue;
+        }
+
+        let reduced: Vec<Lit> = lits.iter().copied().filter(|l| *l != !lit).collect();
+
+        if reduced.is_empty() {
+            return None;
+        }
+
+        conditioned.push(reduced);
+    }
+
+    Some(conditioned)
+}
+
+/// Splits clauses into groups that share no variables.
+fn components(clauses: Vec<Vec<Lit>>) -> Vec<Vec<Vec<Lit>>> {
+    let mut parents: HashMap<Var, Var> = HashMap::new();
+
+    fn find(parents: &mut HashMap<Var, Var>, var: Var) -> Var {
+        let parent = *parents.entry(var).or_insert(var);
+
+        if parent == var {
+            return var;
+        }
+
+        let root = find(parents, parent);
+        parents.insert(var, root);
+
+        root
+    }
+
+    for lits in &clauses {
+        let first = find(&mut parents, lits[0].var());
+
+        for lit in &lits[1..] {
+            let root = find(&mut parents, lit.var());
+            parents.insert(root, first);
+        }
+    }
+
+    let mut groups: HashMap<Var, Vec<Vec<Lit>>> = HashMap::new();
+
+    for lits in clauses {
+        let root = find(&mut parents, lits[0].var());
+        groups.entry(root).or_default().push(lits);
+    }
+
+    groups.into_values().collect()
+}
@@ -0,0 +1,107 @@
+//! Cube-and-conquer
+//!
+//! A lookahead cuber splits a formula into cubes: conjunctions of literals that together cover every model not already refuted by lookahead.
+//! Each cube is then solved independently under assumptions, in parallel with rayon.
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+
+use crate::{
+    lookahead::{look_ahead, Lookahead, Propagator},
+    Formula, Lit, SolveResult, Solver, SolverConfig,
+};
+
+/// The number of variables the cuber looks ahead on at each split.
+const CANDIDATES: usize = 32;
+
+/// Splits `formula` into cubes by branching on lookahead variables, to at most `depth` decisions per cube.
+///
+/// Any model of the formula satisfies one of the cubes. Branches refuted during lookahead are left out, so an unsatisfiable formula may have no cubes.
+pub fn cubes(formula: &Formula, depth: usize) -> Vec<Vec<Lit>> {
+    let mut propagator = Propagator::new(formula);
+    let mut cubes = Vec::new();
+
+    if propagator.is_ok() {
+        split(&mut propagator, &mut Vec::new(), depth, &mut cubes);
+    }
+
+    // Auxiliary variables of the clausal encoding are defined by the others, so dropping them still leaves every model covered
+    let max_var = formula.max_var();
+
+    for cube in &mut cubes {
+        cube.retain(|lit| Some(lit.var()) <= max_var);
+    }
+
+    cubes
+}
+
+fn split(
+    propagator: &mut Propagator,
+    cube: &mut Vec<Lit>,
+    depth: usize,
+    cubes: &mut Vec<Vec<Lit>>,
+) {
+    if depth == 0 || propagator.is_satisfied() {
+        cubes.push(cube.clone());
+        return;
+    }
+
+    let mark = propagator.mark();
+
+    match look_ahead(propagator, CANDIDATES) {
+        Lookahead::Conflict => {}
+        Lookahead::Branch(None) => cubes.push(cube.clone()),
+        Lookahead::Branch(Some(first)) => {
+            for lit in [first, !first] {
+                let branch_mark = propagator.mark();
+
+                if propagator.assign(lit) {
+                    cube.push(lit);
+                    split(propagator, cube, depth - 1, cubes);
+                    cube.pop();
+                }
+
+                propagator.undo(branch_mark);
+            }
+        }
+    }
+
+    propagator.undo(mark);
+}
+
+/// Solves `formula` under each cube in parallel, stopping at the first satisfiable cube.
+///
+/// The first satisfiable cube interrupts the solvers still working on other cubes.
+/// Returns `SolveResult::Unsat` if every cube is unsatisfiable, which is only meaningful if the cubes cover every model, as those from `cubes` do.
+pub fn solve_cubes(formula: &Formula, cubes: &[Vec<Lit>], config: &SolverConfig) -> SolveResult {
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let result = cubes
+        .par_iter()
+        .map_init(
+            || {
+                let mut solver = Solver::from_formula(formula, config.clone());
+                solver.set_interrupt(stop.clone());
+                solver
+            },
+            |solver, cube| solver.solve_with_assumptions(cube),
+        )
+        .find_map_any(|result| match result {
+            SolveResult::Sat(_) => {
+                stop.store(true, Ordering::Relaxed);
+                Some(result)
+            }
+            _ => None,
+        });
+
+    result.unwrap_or(SolveResult::Unsat)
+}
+
+/// Splits `formula` into cubes of up to `depth` decisions and solves them in parallel.
+pub fn cube_and_conquer(formula: &Formula, depth: usize) -> SolveResult {
+    solve_cubes(formula, &cubes(formula, depth), &SolverConfig::default())
+}
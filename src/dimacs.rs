@@ -0,0 +1,317 @@
+//! DIMACS style input and output
+//!
+//! Readers and writers for the text formats used by SAT competitions and their relatives.
+
+use std::fmt::Write;
+
+use crate::{
+    errors::ParseError, formula::clausal, Clause, Formula, Lit, Qbf, Quantifier, Var, VarPool,
+    WeightedFormula, Xor,
+};
+
+/// Parses a CNF formula.
+///
+/// Lines prefixed by `x` are parity constraints, as accepted by CryptoMiniSat: `x1 -2 3 0` requires an odd number of the literals to be true.
+pub fn parse_cnf(input: &str) -> Result<Formula, ParseError> {
+    let mut formula = Formula::new();
+
+    for (line_index, line) in input.lines().enumerate() {
+        let line_number = line_index + 1;
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('c') {
+            continue;
+        }
+
+        if line.starts_with('p') {
+            parse_cnf_header(line.split_whitespace(), line_number)?;
+            continue;
+        }
+
+        if let Some(xor) = line.strip_prefix('x') {
+            let clause = parse_clause(xor.split_whitespace(), line_number)?;
+            formula.add_xor(Xor::new(&clause.literals()));
+            continue;
+        }
+
+        formula.add_clause(parse_clause(line.split_whitespace(), line_number)?);
+    }
+
+    Ok(formula)
+}
+
+/// Writes a CNF formula, with parity constraints on lines prefixed by `x`.
+///
+/// Cardinality constraints have no line of their own, so are replaced by clauses as in `Formula::to_cnf`, with any auxiliary variables after those of the formula.
+pub fn write_cnf(formula: &Formula) -> String {
+    let mut cardinalities = Formula::new();
+
+    for cardinality in formula.cardinalities() {
+        cardinalities.add_cardinality(cardinality.clone());
+    }
+
+    let cardinalities = cardinalities.to_cnf_with(&mut VarPool::after(formula.max_var()));
+    let max_var = formula
+        .max_var()
+        .into_iter()
+        .chain(cardinalities.max_var())
+        .max();
+    let vars = max_var.map_or(0, |var| var.index() + 1);
+    let constraints =
+        formula.clauses().len() + cardinalities.clauses().len() + formula.xors().len();
+    let mut output = format!("p cnf {} {}\n", vars, constraints);
+
+    for clause in formula.clauses().iter().chain(cardinalities.clauses()) {
+        writeln!(output, "{}", format_clause(clause)).unwrap();
+    }
+
+    for xor in formula.xors() {
+        writeln!(output, "x{}", format_clause(&xor.literals().into())).unwrap();
+    }
+
+    output
+}
+
+/// Parses a prenex QBF in the QDIMACS format.
+///
+/// Quantifier blocks are lines prefixed by `e` or `a`, listing positive variables up to a terminating `0`, and must come before the clauses.
+pub fn parse_qdimacs(input: &str) -> Result<Qbf, ParseError> {
+    let mut prefix = Vec::new();
+    let mut matrix = Formula::new();
+
+    for (line_index, line) in input.lines().enumerate() {
+        let line_number = line_index + 1;
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('c') {
+            continue;
+        }
+
+        if line.starts_with('p') {
+            parse_cnf_header(line.split_whitespace(), line_number)?;
+            continue;
+        }
+
+        let quantifier = match line.split_whitespace().next() {
+            Some("e") => Some(Quantifier::Exists),
+            Some("a") => Some(Quantifier::Forall),
+            _ => None,
+        };
+
+        if let Some(quantifier) = quantifier {
+            if !matrix.clauses().is_empty() {
+                return Err(ParseError::InvalidQuantifier(line_number));
+            }
+
+            let block = parse_clause(line.split_whitespace().skip(1), line_number)?;
+
+            if block.literals().iter().any(|lit| lit.is_negative()) {
+                return Err(ParseError::InvalidQuantifier(line_number));
+            }
+
+            let vars: Vec<Var> = block.literals().iter().map(|lit| lit.var()).collect();
+            prefix.push((quantifier, vars));
+            continue;
+        }
+
+        matrix.add_clause(parse_clause(line.split_whitespace(), line_number)?);
+    }
+
+    let mut qbf = Qbf::new(matrix);
+
+    for (quantifier, vars) in prefix {
+        qbf.add_block(quantifier, &vars);
+    }
+
+    Ok(qbf)
+}
+
+/// Writes a prenex QBF in the QDIMACS format.
+///
+/// Parity and cardinality constraints in the matrix are replaced by clauses, with any auxiliary variables in an innermost existential block.
+pub fn write_qdimacs(qbf: &Qbf) -> String {
+    let (prefix, matrix) = qbf.clausal(qbf.prefix().to_vec());
+    let max_var = prefix
+        .iter()
+        .flat_map(|(_, block)| block.iter().copied())
+        .chain(matrix.max_var())
+        .max();
+    let vars = max_var.map_or(0, |var| var.index() + 1);
+    let mut output = format!("p cnf {} {}\n", vars, matrix.clauses().len());
+
+    for (quantifier, block) in &prefix {
+        let letter = match quantifier {
+            Quantifier::Exists => 'e',
+            Quantifier::Forall => 'a',
+        };
+
+        let lits: Clause = block.iter().map(|var| var.positive()).into();
+        writeln!(output, "{} {}", letter, format_clause(&lits)).unwrap();
+    }
+
+    for clause in matrix.clauses() {
+        writeln!(output, "{}", format_clause(clause)).unwrap();
+    }
+
+    output
+}
+
+/// Parses a weighted CNF formula.
+///
+/// Accepts both the pre-2022 format, where a `p wcnf` line gives the weight `top` at or above which clauses are hard, and the 2022 format, where hard clauses are prefixed by `h`.
+pub fn parse_wcnf(input: &str) -> Result<WeightedFormula, ParseError> {
+    let mut instance = WeightedFormula::new();
+    let mut top = None;
+
+    for (line_index, line) in input.lines().enumerate() {
+        let line_number = line_index + 1;
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('c') {
+            continue;
+        }
+
+        let mut tokens = line.split_whitespace();
+
+        if line.starts_with('p') {
+            top = parse_wcnf_header(tokens, line_number)?;
+            continue;
+        }
+
+        let weight = tokens
+            .next()
+            .ok_or(ParseError::InvalidWeight(line_number))?;
+        let clause = parse_clause(tokens, line_number)?;
+
+        if weight == "h" {
+            instance.add_hard(clause);
+            continue;
+        }
+
+        let weight: u64 = weight
+            .parse()
+            .map_err(|_| ParseError::InvalidWeight(line_number))?;
+
+        if top.is_some_and(|top| weight >= top) {
+            instance.add_hard(clause);
+        } else {
+            instance.add_soft(clause, weight);
+        }
+    }
+
+    Ok(instance)
+}
+
+/// Writes a weighted CNF formula in the 2022 format.
+///
+/// Soft clauses of weight zero never add to the cost, so they are left out.
+pub fn write_wcnf(instance: &WeightedFormula) -> String {
+    let mut output = String::new();
+
+    for clause in instance.hard().clauses() {
+        writeln!(output, "h {}", format_clause(clause)).unwrap();
+    }
+
+    for (clause, weight) in instance.soft().iter().filter(|(_, weight)| *weight > 0) {
+        writeln!(output, "{} {}", weight, format_clause(clause)).unwrap();
+    }
+
+    output
+}
+
+/// Writes a formula and cubes in the incremental iCNF format, for solving each cube under assumptions.
+///
+/// The format has only clauses, so parity and cardinality constraints are replaced by clauses as in `Formula::to_cnf`, with any auxiliary variables after those of the formula.
+pub fn write_icnf(formula: &Formula, cubes: &[Vec<Lit>]) -> String {
+    let mut output = String::from("p inccnf\n");
+
+    for clause in clausal(formula).clauses() {
+        writeln!(output, "{}", format_clause(clause)).unwrap();
+    }
+
+    for cube in cubes {
+        writeln!(output, "a {}", format_clause(&cube.iter().copied().into())).unwrap();
+    }
+
+    output
+}
+
+/// Checks a `p cnf <vars> <clauses>` line.
+fn parse_cnf_header<'a>(
+    mut tokens: impl Iterator<Item = &'a str>,
+    line_number: usize,
+) -> Result<(), ParseError> {
+    let mut parse = || -> Option<()> {
+        if tokens.next()? != "p" || tokens.next()? != "cnf" {
+            return None;
+        }
+
+        for _ in 0..2 {
+            tokens.next()?.parse::<usize>().ok()?;
+        }
+
+        tokens.next().is_none().then_some(())
+    };
+
+    parse().ok_or(ParseError::InvalidHeader(line_number))
+}
+
+/// Parses a `p wcnf <vars> <clauses> [top]` line, returning `top` if present.
+///
+/// Without an explicit `top` every clause is soft.
+fn parse_wcnf_header<'a>(
+    mut tokens: impl Iterator<Item = &'a str>,
+    line_number: usize,
+) -> Result<Option<u64>, ParseError> {
+    let mut parse = || -> Option<Option<u64>> {
+        if tokens.next()? != "p" || tokens.next()? != "wcnf" {
+            return None;
+        }
+
+        for _ in 0..2 {
+            tokens.next()?.parse::<usize>().ok()?;
+        }
+
+        match tokens.next() {
+            Some(top) => top.parse().ok().map(Some),
+            None => Some(None),
+        }
+    };
+
+    parse().ok_or(ParseError::InvalidHeader(line_number))
+}
+
+/// Parses literals up to and including the terminating `0`.
+fn parse_clause<'a>(
+    tokens: impl Iterator<Item = &'a str>,
+    line_number: usize,
+) -> Result<Clause, ParseError> {
+    let mut clause = Clause::new();
+
+    for token in tokens {
+        let number: isize = token
+            .parse()
+            .map_err(|_| ParseError::InvalidLiteral(line_number))?;
+
+        if number == 0 {
+            return Ok(clause);
+        }
+
+        let lit = Lit::from_dimacs(number).map_err(|_| ParseError::InvalidLiteral(line_number))?;
+        clause.add_literal(lit);
+    }
+
+    Err(ParseError::UnterminatedClause(line_number))
+}
+
+/// Formats a clause as DIMACS literals followed by the terminating `0`.
+fn format_clause(clause: &Clause) -> String {
+    let mut output = String::new();
+
+    for lit in clause.literals() {
+        write!(output, "{} ", lit.to_dimacs()).unwrap();
+    }
+
+    output.push('0');
+    output
+}
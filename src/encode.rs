@@ -0,0 +1,165 @@
+//! Clausal encodings of constraints
+//!
+//! Each encoding adds clauses to an existing formula, taking any auxiliary variables it needs from a `VarPool`.
+
+use std::collections::BTreeMap;
+
+use crate::{Clause, Formula, Lit, VarPool};
+
+/// Encodes that at most one of `lits` is true, using the pairwise encoding.
+pub(crate) fn at_most_one(lits: &[Lit], formula: &mut Formula) {
+    for (i, a) in lits.iter().enumerate() {
+        for b in &lits[i + 1..] {
+            formula.add_clause([!*a, !*b].into());
+        }
+    }
+}
+
+/// Encodes that at most `bound` of `lits` are true, using a totaliser.
+pub(crate) fn at_most(lits: &[Lit], bound: usize, pool: &mut VarPool, formula: &mut Formula) {
+    let outputs = totaliser(lits, bound + 1, pool, formula);
+
+    if let Some(output) = outputs.get(bound) {
+        formula.add_clause([!*output].into());
+    }
+}
+
+/// Builds a totaliser over `lits`, returning an output for each count up to `cap`: output `j` is true exactly when at least `j + 1` of `lits` are.
+///
+/// Counts are implied in both directions, so the outputs are defined by `lits`. Assuming an output false bounds the count without adding clauses.
+pub(crate) fn totaliser(
+    lits: &[Lit],
+    cap: usize,
+    pool: &mut VarPool,
+    formula: &mut Formula,
+) -> Vec<Lit> {
+    if lits.len() <= 1 {
+        return lits.iter().copied().take(cap).collect();
+    }
+
+    let (left, right) = lits.split_at(lits.len() / 2);
+    let left = totaliser(left, cap, pool, formula);
+    let right = totaliser(right, cap, pool, formula);
+    let outputs: Vec<Lit> = (0..(left.len() + right.len()).min(cap))
+        .map(|_| pool.fresh().positive())
+        .collect();
+
+    for i in 0..=left.len() {
+        for j in 0..=right.len() {
+            // At least `i` true on the left and `j` on the right make at least `i + j`
+            if (1..=outputs.len()).contains(&(i + j)) {
+                let mut clause: Vec<Lit> = i
+                    .checked_sub(1)
+                    .map(|i| !left[i])
+                    .into_iter()
+                    .chain(j.checked_sub(1).map(|j| !right[j]))
+                    .collect();
+                clause.push(outputs[i + j - 1]);
+                formula.add_clause(clause.into());
+            }
+
+            // At most `i` on the left and `j` on the right make at most `i + j`.
+            // A side without an output for `i + 1` has no more than `i` literals, as sides are only capped beyond the outputs needed here.
+            if i + j < outputs.len() {
+                let mut clause: Vec<Lit> = left
+                    .get(i)
+                    .into_iter()
+                    .chain(right.get(j))
+                    .copied()
+                    .collect();
+                clause.push(!outputs[i + j]);
+                formula.add_clause(clause.into());
+            }
+        }
+    }
+
+    outputs
+}
+
+/// Encodes that the total weight of the true literals in `terms` is at most `bound`, using a generalised totaliser.
+pub(crate) fn pb_at_most(
+    terms: &[(Lit, u64)],
+    bound: u64,
+    pool: &mut VarPool,
+    formula: &mut Formula,
+) {
+    if terms.is_empty() {
+        return;
+    }
+
+    let sums = totalise(terms, bound, pool, formula);
+
+    // Sums are capped at one more than the bound, so at most one output can exceed it
+    for (sum, output) in sums {
+        if sum > bound {
+            formula.add_clause([!output].into());
+        }
+    }
+}
+
+/// Builds a totaliser node over `terms`, returning an output literal for each reachable sum.
+///
+/// An output is implied by any combination of inputs reaching its sum. Sums above `bound` are merged into `bound + 1`.
+fn totalise(
+    terms: &[(Lit, u64)],
+    bound: u64,
+    pool: &mut VarPool,
+    formula: &mut Formula,
+) -> BTreeMap<u64, Lit> {
+    if let [(lit, weight)] = terms {
+        return BTreeMap::from([((*weight).min(bound.saturating_add(1)), *lit)]);
+    }
+
+    let (left, right) = terms.split_at(terms.len() / 2);
+    let left = totalise(left, bound, pool, formula);
+    let right = totalise(right, bound, pool, formula);
+
+    let mut outputs = BTreeMap::new();
+    let mut output = |sum: u64, pool: &mut VarPool| -> Lit {
+        *outputs
+            .entry(sum.min(bound.saturating_add(1)))
+            .or_insert_with(|| pool.fresh().positive())
+    };
+
+    for (sum, lit) in left.iter().chain(&right) {
+        let out = output(*sum, pool);
+        formula.add_clause([!*lit, out].into());
+    }
+
+    for (left_sum, left_lit) in &left {
+        for (right_sum, right_lit) in &right {
+            let out = output(left_sum.saturating_add(*right_sum), pool);
+            formula.add_clause(Clause::from([!*left_lit, !*right_lit, out]));
+        }
+    }
+
+    outputs
+}
+
+/// Encodes that an odd number of `lits` are true if `parity` is `true`, or an even number otherwise.
+///
+/// Uses a chain of auxiliary variables, each the exclusive or of the previous one and the next literal.
+pub(crate) fn xor(lits: &[Lit], parity: bool, pool: &mut VarPool, formula: &mut Formula) {
+    let Some((first, rest)) = lits.split_first() else {
+        if parity {
+            formula.add_clause(Clause::new());
+        }
+
+        return;
+    };
+
+    let mut sum = *first;
+
+    for lit in rest {
+        let next = pool.fresh().positive();
+
+        formula.add_clause([!next, sum, *lit].into());
+        formula.add_clause([!next, !sum, !*lit].into());
+        formula.add_clause([next, !sum, *lit].into());
+        formula.add_clause([next, sum, !*lit].into());
+
+        sum = next;
+    }
+
+    formula.add_clause([if parity { sum } else { !sum }].into());
+}
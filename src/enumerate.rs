@@ -0,0 +1,66 @@
+//! Enumeration of all satisfying assignments (AllSAT)
+//!
+//! Enumeration partitions the search space into disjoint cubes of assumptions rather than adding blocking clauses, so the formula never grows.
+//! Each model found under a cube splits the rest of that cube into one sub-cube per remaining variable, each flipping that variable's value in the model.
+
+use crate::{solve_with_assumptions, Assignment, Formula, Lit, Var};
+
+/// An iterator over the models of a formula, projected onto a set of variables.
+///
+/// Each item assigns exactly the projection variables, and no two items are equal.
+pub struct Models<'a> {
+    formula: &'a Formula,
+    vars: Vec<Var>,
+    /// Cubes still to be explored, as assumptions over a prefix of `vars`.
+    pending: Vec<Vec<Lit>>,
+}
+
+impl Iterator for Models<'_> {
+    type Item = Assignment;
+
+    fn next(&mut self) -> Option<Assignment> {
+        while let Some(cube) = self.pending.pop() {
+            let Some(model) = solve_with_assumptions(self.formula, &cube) else {
+                continue;
+            };
+
+            let model = model.completed(&self.vars);
+            let mut prefix = cube.clone();
+
+            // Every other model in this cube differs from `model` on some variable after the cube
+            for var in &self.vars[cube.len()..] {
+                let value = model.get(var).unwrap();
+
+                let mut sibling = prefix.clone();
+                sibling.push(Lit::from_var(var, !value));
+                self.pending.push(sibling);
+
+                prefix.push(Lit::from_var(var, value));
+            }
+
+            return Some(model);
+        }
+
+        None
+    }
+}
+
+/// Enumerates every satisfying assignment of the variables in `formula`.
+pub fn models(formula: &Formula) -> Models<'_> {
+    projected_models(formula, &formula.vars())
+}
+
+/// Enumerates the distinct assignments to `vars` that extend to a model of `formula`.
+///
+/// Variables in `vars` that do not occur in the formula take both values.
+pub fn projected_models<'a>(formula: &'a Formula, vars: &[Var]) -> Models<'a> {
+    let mut vars = vars.to_vec();
+    vars.sort();
+    vars.dedup();
+
+    Models {
+        formula,
+        vars,
+        pending: vec![Vec::new()],
+    }
+}
@@ -0,0 +1,202 @@
+//! Equivalent literal substitution
+//!
+//! Binary clauses form an implication graph, with an edge from `!a` to `b` and from `!b` to `a` for each clause `a | b`.
+//! The literals in a strongly connected component of the graph all imply each other, so each can be replaced by one representative.
+
+use crate::{
+    preprocess::{ClauseDb, PreprocessStats, Reconstruction},
+    Formula, Lit,
+};
+
+/// The implication graph of the binary clauses in a set of clauses.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ImplicationGraph {
+    /// The literals implied by each literal, indexed by `Lit::code`.
+    edges: Vec<Vec<Lit>>,
+}
+
+impl ImplicationGraph {
+    /// Adds the implications of the clause `a | b`.
+    pub fn add_binary(&mut self, a: Lit, b: Lit) {
+        let size = (a.index().max(b.index()) + 1) * 2;
+
+        if self.edges.len() < size {
+            self.edges.resize(size, Vec::new());
+        }
+
+        self.edges[(!a).code()].push(b);
+        self.edges[(!b).code()].push(a);
+    }
+
+    /// The strongly connected components with at least one edge, in reverse topological order.
+    ///
+    /// If a component contains a literal, the complementary component contains its complement.
+    pub fn components(&self) -> Vec<Vec<Lit>> {
+        // Tarjan's algorithm, iterative to avoid overflowing the stack on long implication chains
+        let count = self.edges.len();
+        let mut order = vec![usize::MAX; count];
+        let mut low = vec![0; count];
+        let mut on_stack = vec![false; count];
+        let mut stack = Vec::new();
+        let mut components = Vec::new();
+        let mut next_order = 0;
+
+        for root in 0..count {
+            if order[root] != usize::MAX || self.edges[root].is_empty() {
+                continue;
+            }
+
+            // Each frame is a node and the position of the next edge to follow
+            let mut frames = vec![(root, 0)];
+            order[root] = next_order;
+            low[root] = next_order;
+            next_order += 1;
+            stack.push(root);
+            on_stack[root] = true;
+
+            while let Some((node, position)) = frames.last_mut() {
+                let node = *node;
+
+                if let Some(next) = self.edges[node].get(*position) {
+                    *position += 1;
+                    let next = next.code();
+
+                    if order[next] == usize::MAX {
+                        order[next] = next_order;
+                        low[next] = next_order;
+                        next_order += 1;
+                        stack.push(next);
+                        on_stack[next] = true;
+                        frames.push((next, 0));
+                    } else if on_stack[next] {
+                        low[node] = low[node].min(order[next]);
+                    }
+
+                    continue;
+                }
+
+                frames.pop();
+
+                if let Some((parent, _)) = frames.last() {
+                    low[*parent] = low[*parent].min(low[node]);
+                }
+
+                if low[node] == order[node] {
+                    let mut component = Vec::new();
+
+                    loop {
+                        let member = stack.pop().unwrap();
+                        on_stack[member] = false;
+                        component.push(Lit::from_code(member));
+
+                        if member == node {
+                            break;
+                        }
+                    }
+
+                    components.push(component);
+                }
+            }
+        }
+
+        components
+    }
+}
+
+/// The classes of equivalent literals implied by the binary clauses of `formula`, each with at least two literals.
+///
+/// Each class is sorted, so its first literal has the lowest variable index.
+/// Only one of each pair of complementary classes is returned, the one whose first literal is positive.
+/// A class containing a literal and its complement, which is its own complementary class, shows that `formula` is unsatisfiable.
+pub fn equivalent_literals(formula: &Formula) -> Vec<Vec<Lit>> {
+    let mut graph = ImplicationGraph::default();
+
+    for clause in formula.clauses() {
+        if let [a, b] = clause.literals()[..] {
+            graph.add_binary(a, b);
+        }
+    }
+
+    equivalence_classes(&graph)
+}
+
+fn equivalence_classes(graph: &ImplicationGraph) -> Vec<Vec<Lit>> {
+    graph
+        .components()
+        .into_iter()
+        .filter(|component| component.len() > 1)
+        .map(|mut component| {
+            component.sort();
+            component
+        })
+        .filter(|component| component[0].is_positive() || component[1] == !component[0])
+        .collect()
+}
+
+/// Replaces every equivalent literal by its class representative until no more equivalences are found.
+pub(crate) fn substitute_equivalences(
+    db: &mut ClauseDb,
+    reconstruction: &mut Reconstruction,
+    stats: &mut PreprocessStats,
+) {
+    loop {
+        let mut graph = ImplicationGraph::default();
+
+        for index in db.indices() {
+            if let [a, b] = db.get(index).unwrap()[..] {
+                graph.add_binary(a, b);
+            }
+        }
+
+        let classes = equivalence_classes(&graph);
+
+        if classes.is_empty() {
+            return;
+        }
+
+        // The representative of each literal, indexed by `Lit::code`
+        let mut representatives: Vec<Option<Lit>> = vec![None; graph.edges.len()];
+
+        for class in &classes {
+            let representative = class[0];
+
+            for lit in &class[1..] {
+                if lit.var() == representative.var() {
+                    db.add(Vec::new());
+                    return;
+                }
+
+                representatives[lit.code()] = Some(representative);
+                representatives[(!*lit).code()] = Some(!representative);
+            }
+        }
+
+        let representative = |lit: Lit| representatives.get(lit.code()).copied().flatten();
+
+        for index in db.indices() {
+            let lits = db.get(index).unwrap();
+
+            if lits.iter().any(|lit| representative(*lit).is_some()) {
+                let substituted = db
+                    .remove(index)
+                    .into_iter()
+                    .map(|lit| representative(lit).unwrap_or(lit))
+                    .collect();
+                db.add(substituted);
+            }
+        }
+
+        // Each substituted variable takes the value of its representative, which is reconstructed first
+        for class in &classes {
+            for lit in &class[1..] {
+                reconstruction.push(*lit, vec![*lit, !class[0]]);
+                reconstruction.push(!*lit, vec![!*lit, class[0]]);
+                stats.substituted_vars += 1;
+            }
+        }
+
+        if db.is_unsat() {
+            return;
+        }
+    }
+}
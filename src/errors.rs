@@ -8,3 +8,21 @@ pub enum LitError {
     #[error("Index out of range, cannot be greater than Var::max().index()")]
     IndexTooLarge,
 }
+
+#[derive(Debug, Error)]
+pub enum ParseError {
+    #[error("Line {0}: invalid problem line")]
+    InvalidHeader(usize),
+
+    #[error("Line {0}: invalid weight")]
+    InvalidWeight(usize),
+
+    #[error("Line {0}: invalid literal")]
+    InvalidLiteral(usize),
+
+    #[error("Line {0}: clause is not terminated by 0")]
+    UnterminatedClause(usize),
+
+    #[error("Line {0}: invalid quantifier block")]
+    InvalidQuantifier(usize),
+}
@@ -1,11 +1,11 @@
 //! Clauses and Formulas
 
 use core::panic;
-use std::{collections::HashMap, fmt, ops::Range};
+use std::{borrow::Cow, collections::HashMap, fmt, ops::Range};
 
 use rand::prelude::*;
 
-use crate::{errors::LitError, Lit, Var};
+use crate::{encode, errors::LitError, Lit, SolveResult, Solver, SolverConfig, Var, VarPool};
 
 #[derive(Debug, Default, Clone)]
 pub struct Assignment {
@@ -48,7 +48,7 @@ impl Assignment {
             return Some(assignment == lit.polarity());
         }
 
-        return None;
+        None
     }
 
     /// Returns this assignment as a vector of assignment pairs, sorted by variable index.
@@ -69,10 +69,112 @@ impl Assignment {
         self.vec().iter().map(|(var, value)| Lit::from_var(var, *value)).collect()
     }
 
+    /// Returns an assignment over exactly `vars`, taking values from this assignment and assigning `false` to any variable left unset.
+    pub fn completed(&self, vars: &[Var]) -> Assignment {
+        let mut assignment = Assignment::new();
+
+        for var in vars {
+            assignment.set(*var, self.get(var).unwrap_or(false));
+        }
+
+        assignment
+    }
+
+    /// This assignment without the variables after `max_var`, such as the auxiliary variables of `Formula::to_cnf`.
+    pub(crate) fn up_to(&self, max_var: Option<Var>) -> Assignment {
+        Assignment {
+            assignemnts: self
+                .assignemnts
+                .iter()
+                .filter(|(var, _)| Some(**var) <= max_var)
+                .map(|(var, value)| (*var, *value))
+                .collect(),
+        }
+    }
+
     /// Get a hashmap of variable assignments.
     pub fn hashmap(&self) -> HashMap<Var, bool> {
         self.assignemnts.clone()
     }
+
+    /// Drops assigned variables while every completion of the rest still satisfies `formula`.
+    ///
+    /// The result is a prime implicant of the formula: no further variable can be dropped. Variables not in the formula are always dropped.
+    ///
+    /// # Panics
+    ///
+    /// If this assignment does not satisfy `formula`.
+    pub fn shrink(&self, formula: &Formula) -> Assignment {
+        assert_eq!(
+            formula.evaluate(self),
+            Some(true),
+            "only an assignment satisfying the formula can be shrunk"
+        );
+
+        let mut shrunk = Assignment::new();
+
+        for var in formula.vars() {
+            if let Some(value) = self.get(&var) {
+                shrunk.set(var, value);
+            }
+        }
+
+        // The number of true literals in each clause, and the clauses each true literal is in
+        let mut true_counts = Vec::new();
+        let mut occurrences: Vec<Vec<usize>> = Vec::new();
+
+        for (index, clause) in formula.clauses().iter().enumerate() {
+            let mut lits = clause.literals();
+            lits.sort();
+            lits.dedup();
+            lits.retain(|lit| shrunk.evaluate(lit) == Some(true));
+
+            for lit in &lits {
+                if occurrences.len() <= lit.code() {
+                    occurrences.resize(lit.code() + 1, Vec::new());
+                }
+
+                occurrences[lit.code()].push(index);
+            }
+
+            true_counts.push(lits.len());
+        }
+
+        for lit in shrunk.lits() {
+            let clauses = occurrences
+                .get(lit.code())
+                .map_or(&[][..], |clauses| clauses);
+
+            // Each clause needs a true literal that is kept
+            if clauses.iter().any(|index| true_counts[*index] == 1) {
+                continue;
+            }
+
+            shrunk.assignemnts.remove(&lit.var());
+
+            let decided = formula
+                .xors()
+                .iter()
+                .filter(|xor| xor.vars().contains(&lit.var()))
+                .all(|xor| xor.evaluate(&shrunk) == Some(true))
+                && formula
+                    .cardinalities()
+                    .iter()
+                    .filter(|cardinality| cardinality.lits.iter().any(|l| l.var() == lit.var()))
+                    .all(|cardinality| cardinality.evaluate(&shrunk) == Some(true));
+
+            if !decided {
+                shrunk.set_lit(&lit);
+                continue;
+            }
+
+            for index in clauses {
+                true_counts[*index] -= 1;
+            }
+        }
+
+        shrunk
+    }
 }
 
 /// A CNF clause. That is, a disjunction of literals that themselves can be the complement of a variable.
@@ -146,6 +248,11 @@ impl Clause {
         self.literals.is_empty()
     }
 
+    /// The number of literals in this clause.
+    pub fn len(&self) -> usize {
+        self.literals.len()
+    }
+
     /// Checks if this clause is a unit clause (contains exactly one unassigned literal), based on the provided assignments.
     ///
     /// If it is a unit clause, returns the unit literal, otherwise returns `None`.
@@ -157,10 +264,10 @@ impl Clause {
             .collect();
 
         if unassigned.len() == 1 {
-            return unassigned.get(0).map(|l| **l);
+            return unassigned.first().map(|l| **l);
         }
 
-        return None;
+        None
     }
 
     /// Attempts to evaluate this clause.
@@ -170,7 +277,7 @@ impl Clause {
         let mut decided = true;
 
         for lit in &self.literals {
-            match assignments.evaluate(&lit) {
+            match assignments.evaluate(lit) {
                 Some(value) => match value {
                     true => return Some(true),
                     false => continue,
@@ -228,9 +335,287 @@ impl fmt::Display for Clause {
     }
 }
 
+/// A parity constraint: an odd number of its literals must be true.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Xor {
+    /// Sorted, each occurring at most once.
+    vars: Vec<Var>,
+    /// The value the exclusive or of the variables must take.
+    parity: bool,
+}
+
+impl Xor {
+    /// Creates the constraint that an odd number of `lits` are true.
+    ///
+    /// The constraint is normalised to one over variables: negations flip the parity, and repeated variables cancel.
+    pub fn new(lits: &[Lit]) -> Xor {
+        let negations = lits.iter().filter(|lit| lit.is_negative()).count();
+        let vars: Vec<Var> = lits.iter().map(|lit| lit.var()).collect();
+
+        Xor::from_vars(&vars, negations % 2 == 0)
+    }
+
+    /// Creates the constraint that the exclusive or of `vars` is `parity`.
+    pub fn from_vars(vars: &[Var], parity: bool) -> Xor {
+        let mut vars = vars.to_vec();
+        vars.sort();
+
+        // Pairs of equal variables cancel
+        let mut kept: Vec<Var> = Vec::new();
+
+        for var in vars {
+            if kept.last() == Some(&var) {
+                kept.pop();
+            } else {
+                kept.push(var);
+            }
+        }
+
+        Xor { vars: kept, parity }
+    }
+
+    /// Returns a cloned copy of the variables in this constraint.
+    pub fn vars(&self) -> Vec<Var> {
+        self.vars.clone()
+    }
+
+    /// The value the exclusive or of the variables must take.
+    pub fn parity(&self) -> bool {
+        self.parity
+    }
+
+    /// The literals of this constraint, with the first negated if the parity is even.
+    pub fn literals(&self) -> Vec<Lit> {
+        self.vars
+            .iter()
+            .enumerate()
+            .map(|(i, var)| Lit::from_var(var, i > 0 || self.parity))
+            .collect()
+    }
+
+    /// The number of variables in this constraint.
+    pub fn len(&self) -> usize {
+        self.vars.len()
+    }
+
+    /// Checks if this constraint has no variables, in which case it is satisfied exactly when the parity is even.
+    pub fn is_empty(&self) -> bool {
+        self.vars.is_empty()
+    }
+
+    /// Attempts to evaluate this constraint.
+    ///
+    /// Returns `None` if any variable is unassigned.
+    pub fn evaluate(&self, assignments: &Assignment) -> Option<bool> {
+        let mut value = false;
+
+        for var in &self.vars {
+            value ^= assignments.get(var)?;
+        }
+
+        Some(value == self.parity)
+    }
+
+    /// The clauses equivalent to this constraint, one ruling out each assignment of the wrong parity.
+    ///
+    /// There are `2^(len - 1)` clauses, so this is only practical for short constraints. `Formula::to_cnf` encodes long ones with auxiliary variables instead.
+    ///
+    /// # Panics
+    ///
+    /// If the constraint has 64 or more variables.
+    pub fn clauses(&self) -> Vec<Clause> {
+        let len = self.vars.len();
+
+        assert!(
+            len < 64,
+            "Xor::clauses given a constraint over {} variables, too many clauses to list",
+            len
+        );
+
+        if len == 0 {
+            return if self.parity {
+                vec![Clause::new()]
+            } else {
+                Vec::new()
+            };
+        }
+
+        // Each clause is falsified by exactly one assignment, the one making its negated variables true
+        (0..1u64 << len)
+            .filter(|negations| (negations.count_ones() % 2 == 1) != self.parity)
+            .map(|negations| {
+                self.vars
+                    .iter()
+                    .enumerate()
+                    .map(|(i, var)| Lit::from_var(var, negations >> i & 1 == 0))
+                    .collect::<Vec<Lit>>()
+                    .into()
+            })
+            .collect()
+    }
+}
+
+impl fmt::Debug for Xor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "x{}",
+            self.literals()
+                .iter()
+                .rfold(String::new(), |acc, lit| format!("{:?} {}", lit, acc))
+        )
+    }
+}
+
+/// Uses Debug output
+impl fmt::Display for Xor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// A cardinality constraint: at most `bound` of its literals can be true.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Cardinality {
+    /// Sorted and duplicate free.
+    lits: Vec<Lit>,
+    bound: usize,
+}
+
+impl Cardinality {
+    /// Creates the constraint that at most `bound` of `lits` are true. Repeated literals are counted once.
+    ///
+    /// A literal and its complement always have exactly one true between them, so both are dropped and the bound lowered by one.
+    /// If there are more such pairs than `bound`, the rest are kept, and the constraint is unsatisfiable.
+    pub fn at_most(lits: &[Lit], bound: usize) -> Cardinality {
+        let mut lits = lits.to_vec();
+        lits.sort();
+        lits.dedup();
+
+        let mut kept: Vec<Lit> = Vec::new();
+        let mut bound = bound;
+
+        // Sorting puts each literal next to its complement
+        for lit in lits {
+            if bound > 0 && kept.last() == Some(&!lit) {
+                kept.pop();
+                bound -= 1;
+            } else {
+                kept.push(lit);
+            }
+        }
+
+        Cardinality { lits: kept, bound }
+    }
+
+    /// Creates the constraint that at least `bound` of `lits` are true, as at most all but `bound` of their complements.
+    ///
+    /// # Panics
+    ///
+    /// If `bound` is more than the number of distinct literals.
+    pub fn at_least(lits: &[Lit], bound: usize) -> Cardinality {
+        let mut complements: Vec<Lit> = lits.iter().map(|lit| !*lit).collect();
+        complements.sort();
+        complements.dedup();
+
+        let bound = complements
+            .len()
+            .checked_sub(bound)
+            .expect("at_least given a bound larger than the number of literals");
+
+        Cardinality::at_most(&complements, bound)
+    }
+
+    /// Returns a cloned copy of the literals in this constraint.
+    pub fn literals(&self) -> Vec<Lit> {
+        self.lits.clone()
+    }
+
+    /// The most literals that can be true.
+    pub fn bound(&self) -> usize {
+        self.bound
+    }
+
+    /// Attempts to evaluate this constraint.
+    ///
+    /// Returns `None` if evaluation is not possible.
+    pub fn evaluate(&self, assignments: &Assignment) -> Option<bool> {
+        let mut true_count = 0;
+        let mut unassigned = 0;
+
+        for lit in &self.lits {
+            match assignments.evaluate(lit) {
+                Some(true) => true_count += 1,
+                Some(false) => {}
+                None => unassigned += 1,
+            }
+        }
+
+        if true_count > self.bound {
+            Some(false)
+        } else if true_count + unassigned <= self.bound {
+            Some(true)
+        } else {
+            None
+        }
+    }
+
+    /// The clauses equivalent to this constraint, one for each set of `bound + 1` literals requiring one to be false.
+    ///
+    /// There are `len` choose `bound + 1` clauses, so this is only practical for small bounds.
+    pub fn clauses(&self) -> Vec<Clause> {
+        let mut clauses = Vec::new();
+        let mut chosen = Vec::new();
+
+        choose(&self.lits, self.bound + 1, &mut chosen, &mut clauses);
+
+        clauses
+    }
+}
+
+/// Adds a clause of the complements of each way of choosing `count` more literals from `lits` to `chosen`.
+fn choose(lits: &[Lit], count: usize, chosen: &mut Vec<Lit>, clauses: &mut Vec<Clause>) {
+    if count == 0 {
+        clauses.push(chosen.iter().map(|lit| !*lit).collect::<Vec<Lit>>().into());
+        return;
+    }
+
+    for i in 0..lits.len() {
+        if lits.len() - i < count {
+            break;
+        }
+
+        chosen.push(lits[i]);
+        choose(&lits[i + 1..], count - 1, chosen, clauses);
+        chosen.pop();
+    }
+}
+
+impl fmt::Debug for Cardinality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}<= {}",
+            self.lits
+                .iter()
+                .rfold(String::new(), |acc, lit| format!("{:?} {}", lit, acc)),
+            self.bound
+        )
+    }
+}
+
+/// Uses Debug output
+impl fmt::Display for Cardinality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
 #[derive(Default, Clone)]
 pub struct Formula {
     clauses: Vec<Clause>,
+    xors: Vec<Xor>,
+    cardinalities: Vec<Cardinality>,
 }
 
 impl Formula {
@@ -249,6 +634,212 @@ impl Formula {
         &self.clauses
     }
 
+    /// Adds a parity constraint to this formula.
+    pub fn add_xor(&mut self, xor: Xor) {
+        self.xors.push(xor);
+    }
+
+    /// Gets the parity constraints in this formula.
+    pub fn xors(&self) -> &Vec<Xor> {
+        &self.xors
+    }
+
+    /// Adds a cardinality constraint to this formula.
+    pub fn add_cardinality(&mut self, cardinality: Cardinality) {
+        self.cardinalities.push(cardinality);
+    }
+
+    /// Gets the cardinality constraints in this formula.
+    pub fn cardinalities(&self) -> &Vec<Cardinality> {
+        &self.cardinalities
+    }
+
+    /// An equisatisfiable formula with every parity and cardinality constraint replaced by clauses.
+    ///
+    /// Short constraints are replaced by their own clauses. Longer ones are encoded with auxiliary variables after `max_var`, each defined by the variables of its constraint,
+    /// so every model of this formula extends to exactly one model of the result, and restricting a model of the result to the variables of this formula gives a model of it.
+    pub fn to_cnf(&self) -> Formula {
+        self.to_cnf_with(&mut VarPool::after(self.max_var()))
+    }
+
+    /// Replaces constraints by clauses as `to_cnf` does, taking auxiliary variables from `pool`.
+    pub(crate) fn to_cnf_with(&self, pool: &mut VarPool) -> Formula {
+        let mut formula = Formula::new();
+        formula.clauses = self.clauses.clone();
+
+        for xor in &self.xors {
+            // The chain encoding takes 4 clauses per auxiliary variable, so is only smaller than the 2^(len - 1) clauses from 6 variables
+            if xor.len() <= 5 {
+                formula.clauses.extend(xor.clauses());
+            } else {
+                encode::xor(&xor.literals(), true, pool, &mut formula);
+            }
+        }
+
+        for cardinality in &self.cardinalities {
+            let len = cardinality.lits.len();
+
+            // With room for no literals, or all but one, there are at most `len` clauses
+            if cardinality.bound == 0 || cardinality.bound + 2 >= len {
+                formula.clauses.extend(cardinality.clauses());
+            } else {
+                encode::at_most(&cardinality.lits, cardinality.bound, pool, &mut formula);
+            }
+        }
+
+        formula
+    }
+
+    /// Splits this formula into formulas that share no variables, whose conjunction is this formula.
+    ///
+    /// Components are ordered by their lowest variable. Constraints without variables, such as the empty clause, each form their own component, last.
+    pub fn components(&self) -> Vec<Formula> {
+        let count = self.max_var().map_or(0, |var| var.index() + 1);
+        let mut parents: Vec<usize> = (0..count).collect();
+
+        fn find(parents: &mut [usize], index: usize) -> usize {
+            let mut root = index;
+
+            while parents[root] != root {
+                root = parents[root];
+            }
+
+            // Point everything on the path directly at the root
+            let mut index = index;
+
+            while parents[index] != root {
+                let next = parents[index];
+                parents[index] = root;
+                index = next;
+            }
+
+            root
+        }
+
+        let constraint_vars: Vec<Vec<usize>> = self
+            .clauses
+            .iter()
+            .map(|clause| clause.literals.iter().map(|lit| lit.index()).collect())
+            .chain(
+                self.xors
+                    .iter()
+                    .map(|xor| xor.vars.iter().map(|var| var.index()).collect()),
+            )
+            .chain(
+                self.cardinalities
+                    .iter()
+                    .map(|cardinality| cardinality.lits.iter().map(|lit| lit.index()).collect()),
+            )
+            .collect();
+
+        let mut occurs = vec![false; count];
+
+        for vars in &constraint_vars {
+            if let Some(first) = vars.first() {
+                let first = find(&mut parents, *first);
+
+                for var in vars {
+                    occurs[*var] = true;
+                    let root = find(&mut parents, *var);
+                    parents[root] = first;
+                }
+            }
+        }
+
+        // Number the components in order of their lowest variable
+        let mut numbers: Vec<Option<usize>> = vec![None; count];
+        let mut components = Vec::new();
+
+        for index in (0..count).filter(|index| occurs[*index]) {
+            let root = find(&mut parents, index);
+
+            if numbers[root].is_none() {
+                numbers[root] = Some(components.len());
+                components.push(Formula::new());
+            }
+        }
+
+        let mut targets = constraint_vars
+            .iter()
+            .map(|vars| {
+                vars.first()
+                    .map(|var| numbers[find(&mut parents, *var)].unwrap())
+            })
+            .collect::<Vec<Option<usize>>>()
+            .into_iter();
+        let mut empty = Vec::new();
+
+        fn target<'a>(
+            components: &'a mut [Formula],
+            empty: &'a mut Vec<Formula>,
+            number: Option<usize>,
+        ) -> &'a mut Formula {
+            match number {
+                Some(number) => &mut components[number],
+                None => {
+                    empty.push(Formula::new());
+                    empty.last_mut().unwrap()
+                }
+            }
+        }
+
+        for clause in &self.clauses {
+            target(&mut components, &mut empty, targets.next().unwrap()).add_clause(clause.clone());
+        }
+
+        for xor in &self.xors {
+            target(&mut components, &mut empty, targets.next().unwrap()).add_xor(xor.clone());
+        }
+
+        for cardinality in &self.cardinalities {
+            target(&mut components, &mut empty, targets.next().unwrap())
+                .add_cardinality(cardinality.clone());
+        }
+
+        components.extend(empty);
+
+        components
+    }
+
+    /// Whether this formula has only clauses.
+    pub fn is_cnf(&self) -> bool {
+        self.xors.is_empty() && self.cardinalities.is_empty()
+    }
+
+    /// Returns the variables that occur in this formula, sorted by index.
+    pub fn vars(&self) -> Vec<Var> {
+        let mut vars: Vec<Var> = self
+            .clauses
+            .iter()
+            .flat_map(|clause| clause.literals.iter().map(|lit| lit.var()))
+            .chain(self.xors.iter().flat_map(|xor| xor.vars.iter().copied()))
+            .chain(
+                self.cardinalities
+                    .iter()
+                    .flat_map(|cardinality| cardinality.lits.iter().map(|lit| lit.var())),
+            )
+            .collect();
+
+        vars.sort();
+        vars.dedup();
+
+        vars
+    }
+
+    /// The variable with the largest index in this formula, or `None` if it contains no literals.
+    pub fn max_var(&self) -> Option<Var> {
+        self.clauses
+            .iter()
+            .flat_map(|clause| clause.literals.iter().map(|lit| lit.var()))
+            .chain(self.xors.iter().flat_map(|xor| xor.vars.iter().copied()))
+            .chain(
+                self.cardinalities
+                    .iter()
+                    .flat_map(|cardinality| cardinality.lits.iter().map(|lit| lit.var())),
+            )
+            .max()
+    }
+
     /// Attempts to evaluate the formula using the given assignments.
     pub fn evaluate(&self, assignments: &Assignment) -> Option<bool> {
         let mut decided = true;
@@ -261,12 +852,120 @@ impl Formula {
             }
         }
 
+        for xor in &self.xors {
+            match xor.evaluate(assignments) {
+                Some(true) => continue,
+                Some(false) => return Some(false),
+                None => decided = false,
+            }
+        }
+
+        for cardinality in &self.cardinalities {
+            match cardinality.evaluate(assignments) {
+                Some(true) => continue,
+                Some(false) => return Some(false),
+                None => decided = false,
+            }
+        }
+
         if decided {
             Some(true)
         } else {
             None
         }
     }
+
+    /// Checks that every model of this formula satisfies `other`, over the variables of both.
+    ///
+    /// Each clause of `other` is checked by assuming its literals are all false, and each parity or cardinality constraint by solving with its negation added.
+    /// Returns a counterexample on failure: a model of this formula that falsifies `other`, assigning every variable of both formulas.
+    pub fn entails(&self, other: &Formula) -> Result<(), Assignment> {
+        let mut vars = self.vars();
+        vars.extend(other.vars());
+        vars.sort();
+        vars.dedup();
+
+        let mut solver = Solver::from_formula(self, SolverConfig::default());
+
+        for clause in other.clauses() {
+            let assumptions: Vec<Lit> = clause.literals.iter().map(|lit| !*lit).collect();
+
+            if let SolveResult::Sat(model) = solver.solve_with_assumptions(&assumptions) {
+                return Err(model.completed(&vars));
+            }
+        }
+
+        let mut negations = Vec::new();
+
+        for xor in other.xors() {
+            let mut negation = Formula::new();
+            negation.add_xor(Xor::from_vars(&xor.vars, !xor.parity));
+            negations.push(negation);
+        }
+
+        // More literals than the bound must be true, which is impossible if there are not that many
+        for cardinality in other.cardinalities() {
+            if cardinality.bound < cardinality.lits.len() {
+                let mut negation = Formula::new();
+                negation.add_cardinality(Cardinality::at_least(
+                    &cardinality.lits,
+                    cardinality.bound + 1,
+                ));
+                negations.push(negation);
+            }
+        }
+
+        for negation in negations {
+            let mut solver = Solver::from_formula(self, SolverConfig::default());
+            solver.add_formula(&negation);
+
+            if let SolveResult::Sat(model) = solver.solve() {
+                return Err(model.completed(&vars));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that this formula and `other` have the same models, over the variables of both.
+    ///
+    /// Returns a counterexample on failure: an assignment to every variable of both formulas that satisfies exactly one of them.
+    pub fn equivalent(&self, other: &Formula) -> Result<(), Assignment> {
+        self.entails(other)?;
+        other.entails(self)
+    }
+
+    /// Checks that every assignment to `vars` that extends to a model of this formula also extends to a model of `other`.
+    ///
+    /// Variables outside `vars` are existentially quantified, so formulas that differ only in auxiliary variables can be compared.
+    /// Each model of this formula is checked by assuming its projection in `other`, and is then blocked.
+    /// Returns a counterexample on failure: an assignment to exactly `vars` that extends to a model of this formula but not of `other`.
+    pub fn entails_projected(&self, other: &Formula, vars: &[Var]) -> Result<(), Assignment> {
+        let mut solver = Solver::from_formula(self, SolverConfig::default());
+        let mut checker = Solver::from_formula(other, SolverConfig::default());
+
+        while let SolveResult::Sat(model) = solver.solve() {
+            let projection = model.completed(vars);
+            let lits = projection.lits();
+
+            if checker.solve_with_assumptions(&lits).is_unsat() {
+                return Err(projection);
+            }
+
+            let block: Vec<Lit> = lits.iter().map(|lit| !*lit).collect();
+            solver.add_clause(&block.into());
+        }
+
+        Ok(())
+    }
+
+    /// Checks that this formula and `other` have the same models when projected onto `vars`, see `entails_projected`.
+    ///
+    /// Returns a counterexample on failure: an assignment to exactly `vars` that extends to a model of exactly one of the formulas.
+    pub fn equivalent_projected(&self, other: &Formula, vars: &[Var]) -> Result<(), Assignment> {
+        self.entails_projected(other, vars)?;
+        other.entails_projected(self, vars)
+    }
 }
 
 impl fmt::Debug for Formula {
@@ -275,6 +974,14 @@ impl fmt::Debug for Formula {
             writeln!(f, "{:?}", clause)?
         }
 
+        for xor in self.xors() {
+            writeln!(f, "{:?}", xor)?
+        }
+
+        for cardinality in self.cardinalities() {
+            writeln!(f, "{:?}", cardinality)?
+        }
+
         Ok(())
     }
 }
@@ -285,6 +992,40 @@ impl fmt::Display for Formula {
             writeln!(f, "{}", clause)?
         }
 
+        for xor in self.xors() {
+            writeln!(f, "{}", xor)?
+        }
+
+        for cardinality in self.cardinalities() {
+            writeln!(f, "{}", cardinality)?
+        }
+
         Ok(())
     }
 }
+
+/// `formula` with its parity and cardinality constraints replaced by clauses, for algorithms that only handle clauses.
+///
+/// Any auxiliary variables follow `formula.max_var()`, see `Formula::to_cnf`.
+pub(crate) fn clausal(formula: &Formula) -> Cow<'_, Formula> {
+    clausal_beside(formula, []).0
+}
+
+/// Replaces constraints by clauses as `clausal` does, for callers that also work with `vars`, which may be outside the formula.
+///
+/// Auxiliary variables must not clash with those, so follow both the formula and `vars`. Also returns the last variable that is not auxiliary.
+pub(crate) fn clausal_beside(
+    formula: &Formula,
+    vars: impl IntoIterator<Item = Var>,
+) -> (Cow<'_, Formula>, Option<Var>) {
+    let max_var = formula.max_var().into_iter().chain(vars).max();
+
+    if formula.is_cnf() {
+        (Cow::Borrowed(formula), max_var)
+    } else {
+        (
+            Cow::Owned(formula.to_cnf_with(&mut VarPool::after(max_var))),
+            max_var,
+        )
+    }
+}
@@ -0,0 +1,135 @@
+//! Horn and dual-Horn formulas
+//!
+//! A Horn formula has at most one positive literal per clause, and is satisfiable exactly when unit propagation from all variables false finds no conflict.
+//! The variables propagation makes true form the unique minimal model, which is contained in every other model.
+//! Dual-Horn formulas, with at most one negative literal per clause, are solved the same way with the polarities swapped.
+
+use crate::{Assignment, Formula};
+
+/// Whether every clause of `formula` has at most one positive literal.
+pub fn is_horn(formula: &Formula) -> bool {
+    has_at_most_one(formula, true)
+}
+
+/// Whether every clause of `formula` has at most one negative literal.
+pub fn is_dual_horn(formula: &Formula) -> bool {
+    has_at_most_one(formula, false)
+}
+
+/// Solves a Horn formula in linear time, returning its minimal model.
+///
+/// Returns `None` if the formula is unsatisfiable. Otherwise, the assignment assigns every variable in the formula.
+///
+/// # Panics
+///
+/// If the formula is not Horn.
+pub fn solve_horn(formula: &Formula) -> Option<Assignment> {
+    assert!(
+        is_horn(formula),
+        "solve_horn given a formula that is not Horn"
+    );
+
+    forced_model(formula, true)
+}
+
+/// Solves a dual-Horn formula in linear time, returning its maximal model.
+///
+/// Returns `None` if the formula is unsatisfiable. Otherwise, the assignment assigns every variable in the formula.
+///
+/// # Panics
+///
+/// If the formula is not dual-Horn.
+pub fn solve_dual_horn(formula: &Formula) -> Option<Assignment> {
+    assert!(
+        is_dual_horn(formula),
+        "solve_dual_horn given a formula that is not dual-Horn"
+    );
+
+    forced_model(formula, false)
+}
+
+/// The unique model of a Horn formula with the fewest true variables, which every model makes true.
+///
+/// Returns `None` if the formula is unsatisfiable.
+///
+/// # Panics
+///
+/// If the formula is not Horn.
+pub fn minimal_model(formula: &Formula) -> Option<Assignment> {
+    solve_horn(formula)
+}
+
+fn has_at_most_one(formula: &Formula, polarity: bool) -> bool {
+    formula.is_cnf()
+        && formula.clauses().iter().all(|clause| {
+            let mut lits = clause.literals();
+            lits.retain(|lit| lit.polarity() == polarity);
+            lits.dedup();
+
+            lits.len() <= 1
+        })
+}
+
+/// Starts with every variable `!head`, then sets variables to `head` only when some clause forces it.
+///
+/// Each clause must have at most one literal with polarity `head`, and counts its other literals not yet false.
+fn forced_model(formula: &Formula, head: bool) -> Option<Assignment> {
+    let vars = formula.vars();
+    let count = formula.max_var().map_or(0, |var| var.index() + 1);
+
+    let mut heads = Vec::new();
+    let mut remaining = Vec::new();
+    // The clauses each variable occurs in with polarity `!head`
+    let mut occurrences = vec![Vec::new(); count];
+    let mut forced = vec![false; count];
+    let mut queue = Vec::new();
+
+    for (index, clause) in formula.clauses().iter().enumerate() {
+        let mut lits = clause.literals();
+        lits.sort();
+        lits.dedup();
+
+        heads.push(
+            lits.iter()
+                .find(|lit| lit.polarity() == head)
+                .map(|lit| lit.var()),
+        );
+        remaining.push(0);
+
+        for lit in lits.iter().filter(|lit| lit.polarity() != head) {
+            occurrences[lit.index()].push(index);
+            remaining[index] += 1;
+        }
+
+        if remaining[index] == 0 {
+            queue.push(index);
+        }
+    }
+
+    // Clauses whose other literals are all false, so the head must be true
+    while let Some(index) = queue.pop() {
+        let var = heads[index]?;
+
+        if forced[var.index()] {
+            continue;
+        }
+
+        forced[var.index()] = true;
+
+        for other in &occurrences[var.index()] {
+            remaining[*other] -= 1;
+
+            if remaining[*other] == 0 {
+                queue.push(*other);
+            }
+        }
+    }
+
+    let mut assignment = Assignment::new();
+
+    for var in vars {
+        assignment.set(var, forced[var.index()] == head);
+    }
+
+    Some(assignment)
+}
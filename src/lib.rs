@@ -6,11 +6,56 @@
 //! [cnf]: https://en.wikipedia.org/wiki/Conjunctive_normal_form
 //! [varisat]: https://github.com/jix/varisat
 
+mod approx;
+mod backbone;
+mod cdcl;
+mod count;
+mod cube;
+mod dimacs;
+mod encode;
+mod enumerate;
+mod equivalence;
 pub mod errors;
 mod formula;
+mod horn;
 mod literals;
+mod local_search;
+mod lookahead;
+mod maxsat;
+mod mcs;
+mod minimal;
+mod mus;
+mod portfolio;
+mod preprocess;
+mod qbf;
+mod sample;
 mod solver;
+mod subsume;
+mod symmetry;
+mod two_sat;
 
+pub use approx::*;
+pub use backbone::*;
+pub use cdcl::*;
+pub use count::*;
+pub use cube::*;
+pub use dimacs::*;
+pub use enumerate::*;
+pub use equivalence::*;
 pub use formula::*;
+pub use horn::*;
 pub use literals::*;
+pub use local_search::*;
+pub use lookahead::*;
+pub use maxsat::*;
+pub use mcs::*;
+pub use minimal::*;
+pub use mus::*;
+pub use portfolio::*;
+pub use preprocess::*;
+pub use qbf::*;
+pub use sample::*;
 pub use solver::*;
+pub use subsume::*;
+pub use symmetry::*;
+pub use two_sat::*;
@@ -57,7 +57,7 @@ impl Var {
     /// This allows `Lit` to store polarity information within the index.
     pub const fn max_var() -> Var {
         Var {
-            index: LitIndex::max_value() >> 2,
+            index: LitIndex::MAX >> 2,
         }
     }
 
@@ -100,8 +100,31 @@ impl fmt::Display for Var {
     }
 }
 
+/// Hands out variables that do not occur in a formula, for encodings that need auxiliary variables.
+#[derive(Debug, Clone)]
+pub(crate) struct VarPool {
+    next: usize,
+}
+
+impl VarPool {
+    /// Creates a pool whose first variable follows `max_var`, or the first variable if `None`.
+    pub fn after(max_var: Option<Var>) -> VarPool {
+        VarPool {
+            next: max_var.map_or(0, |var| var.index() + 1),
+        }
+    }
+
+    /// Returns a variable that has not been handed out before.
+    pub fn fresh(&mut self) -> Var {
+        let var = Var::from_index(self.next).expect("ran out of variables");
+        self.next += 1;
+
+        var
+    }
+}
+
 /// A boolean literal.
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Lit {
     /// The code of this literal.
     ///
@@ -150,6 +173,18 @@ impl Lit {
         (self.code >> 1) as usize
     }
 
+    /// A dense 0-based index for this literal, for tables indexed by literal.
+    pub(crate) fn code(&self) -> usize {
+        self.code as usize
+    }
+
+    /// The literal with the given `code`.
+    pub(crate) fn from_code(code: usize) -> Lit {
+        Lit {
+            code: code as LitIndex,
+        }
+    }
+
     /// The underlying variable of this literal.
     pub fn var(&self) -> Var {
         Var {
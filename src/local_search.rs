@@ -0,0 +1,283 @@
+//! Stochastic local search
+//!
+//! WalkSAT and probSAT start from a random assignment and repeatedly flip a variable of a falsified clause, preferring flips that falsify few other clauses.
+//! Local search cannot prove unsatisfiability, but on large satisfiable random instances it is often much faster than DPLL.
+
+use std::time::{Duration, Instant};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{formula::clausal, Assignment, Formula, Lit, SolveResult, Var};
+
+/// The rule used to pick which variable of a falsified clause to flip.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LocalSearchAlgorithm {
+    /// Flips a variable that breaks no clauses if there is one.
+    /// Otherwise, with probability `noise` flips a random variable, and otherwise the one that breaks fewest.
+    WalkSat { noise: f64 },
+    /// Flips a variable chosen with probability proportional to `(1 + break)^-cb`.
+    ProbSat { cb: f64 },
+}
+
+/// Configuration for a local search run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalSearchConfig {
+    pub algorithm: LocalSearchAlgorithm,
+    /// The number of flips after which the search gives up.
+    pub max_flips: u64,
+    /// The duration after which the search gives up, if any.
+    pub time_limit: Option<Duration>,
+    pub seed: u64,
+}
+
+impl Default for LocalSearchConfig {
+    fn default() -> LocalSearchConfig {
+        LocalSearchConfig {
+            algorithm: LocalSearchAlgorithm::ProbSat { cb: 2.3 },
+            max_flips: 1_000_000,
+            time_limit: None,
+            seed: 0,
+        }
+    }
+}
+
+/// Searches for a satisfying assignment by local search.
+///
+/// Returns `SolveResult::Unknown` when a limit is reached. The result is only `SolveResult::Unsat` if the formula contains an empty clause.
+///
+/// # Panics
+///
+/// If the WalkSAT `noise` is not between 0 and 1, or the probSAT `cb` is negative or not finite.
+pub fn local_search(formula: &Formula, config: &LocalSearchConfig) -> SolveResult {
+    match config.algorithm {
+        LocalSearchAlgorithm::WalkSat { noise } => {
+            assert!(
+                (0.0..=1.0).contains(&noise),
+                "noise must be between 0 and 1"
+            )
+        }
+        LocalSearchAlgorithm::ProbSat { cb } => {
+            assert!(
+                cb.is_finite() && cb >= 0.0,
+                "cb must be finite and non-negative"
+            )
+        }
+    }
+
+    let vars = formula.vars();
+    let formula = &*clausal(formula);
+    let mut clauses: Vec<Vec<Lit>> = Vec::new();
+
+    for clause in formula.clauses() {
+        let mut lits = clause.literals();
+        lits.sort();
+        lits.dedup();
+
+        if lits.is_empty() {
+            return SolveResult::Unsat;
+        }
+
+        if lits.windows(2).all(|pair| pair[0] != !pair[1]) {
+            clauses.push(lits);
+        }
+    }
+
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let mut state = State::new(clauses, formula.max_var(), &mut rng);
+    let start = Instant::now();
+
+    for flip in 0..config.max_flips {
+        if state.unsat.is_empty() {
+            break;
+        }
+
+        if flip % 1024 == 0
+            && config
+                .time_limit
+                .is_some_and(|limit| start.elapsed() >= limit)
+        {
+            return SolveResult::Unknown;
+        }
+
+        let clause = state.unsat[rng.random_range(0..state.unsat.len())];
+        let var = match config.algorithm {
+            LocalSearchAlgorithm::WalkSat { noise } => state.pick_walksat(clause, noise, &mut rng),
+            LocalSearchAlgorithm::ProbSat { cb } => state.pick_probsat(clause, cb, &mut rng),
+        };
+
+        state.flip(var);
+    }
+
+    if !state.unsat.is_empty() {
+        return SolveResult::Unknown;
+    }
+
+    let mut assignment = Assignment::new();
+
+    for var in vars {
+        assignment.set(var, state.values[var.index()]);
+    }
+
+    SolveResult::Sat(assignment)
+}
+
+/// The current assignment, with the counts needed to score flips in constant time.
+struct State {
+    clauses: Vec<Vec<Lit>>,
+    values: Vec<bool>,
+    /// Clause indices containing each literal, indexed by `Lit::code`.
+    occurrences: Vec<Vec<usize>>,
+    /// The number of true literals in each clause.
+    true_count: Vec<usize>,
+    /// The exclusive or of the variable indices of true literals in each clause.
+    /// When exactly one literal is true, this is its variable.
+    true_xor: Vec<usize>,
+    /// The number of clauses each variable is the only true literal of, which flipping it would break.
+    breaks: Vec<usize>,
+    unsat: Vec<usize>,
+    /// The position of each clause in `unsat`, if it is falsified.
+    unsat_position: Vec<Option<usize>>,
+}
+
+impl State {
+    fn new(clauses: Vec<Vec<Lit>>, max_var: Option<Var>, rng: &mut impl Rng) -> State {
+        let var_count = max_var.map_or(0, |var| var.index() + 1);
+        let values: Vec<bool> = (0..var_count).map(|_| rng.random_bool(0.5)).collect();
+
+        let mut state = State {
+            occurrences: vec![Vec::new(); var_count * 2],
+            true_count: vec![0; clauses.len()],
+            true_xor: vec![0; clauses.len()],
+            breaks: vec![0; var_count],
+            unsat: Vec::new(),
+            unsat_position: vec![None; clauses.len()],
+            values,
+            clauses,
+        };
+
+        for (i, lits) in state.clauses.iter().enumerate() {
+            for lit in lits {
+                state.occurrences[lit.code()].push(i);
+
+                if state.is_true(lit) {
+                    state.true_count[i] += 1;
+                    state.true_xor[i] ^= lit.index();
+                }
+            }
+
+            match state.true_count[i] {
+                0 => {
+                    state.unsat_position[i] = Some(state.unsat.len());
+                    state.unsat.push(i);
+                }
+                1 => state.breaks[state.true_xor[i]] += 1,
+                _ => {}
+            }
+        }
+
+        state
+    }
+
+    fn is_true(&self, lit: &Lit) -> bool {
+        lit.evaluate(self.values[lit.index()])
+    }
+
+    fn flip(&mut self, var: usize) {
+        self.values[var] = !self.values[var];
+
+        let made_true = Lit::from_index(var, self.values[var]).unwrap();
+
+        // Taken out while the counts are updated, to avoid copying them on every flip
+        let made_true_occurrences = std::mem::take(&mut self.occurrences[made_true.code()]);
+        let made_false_occurrences = std::mem::take(&mut self.occurrences[(!made_true).code()]);
+
+        for &i in &made_true_occurrences {
+            self.true_count[i] += 1;
+            self.true_xor[i] ^= var;
+
+            match self.true_count[i] {
+                1 => {
+                    self.remove_unsat(i);
+                    self.breaks[var] += 1;
+                }
+                2 => self.breaks[self.true_xor[i] ^ var] -= 1,
+                _ => {}
+            }
+        }
+
+        for &i in &made_false_occurrences {
+            self.true_count[i] -= 1;
+            self.true_xor[i] ^= var;
+
+            match self.true_count[i] {
+                0 => {
+                    self.unsat_position[i] = Some(self.unsat.len());
+                    self.unsat.push(i);
+                    self.breaks[var] -= 1;
+                }
+                1 => self.breaks[self.true_xor[i]] += 1,
+                _ => {}
+            }
+        }
+
+        self.occurrences[made_true.code()] = made_true_occurrences;
+        self.occurrences[(!made_true).code()] = made_false_occurrences;
+    }
+
+    fn remove_unsat(&mut self, clause: usize) {
+        let position = self.unsat_position[clause].take().unwrap();
+        self.unsat.swap_remove(position);
+
+        if let Some(moved) = self.unsat.get(position) {
+            self.unsat_position[*moved] = Some(position);
+        }
+    }
+
+    fn pick_walksat(&self, clause: usize, noise: f64, rng: &mut impl Rng) -> usize {
+        let lits = &self.clauses[clause];
+        let min_break = lits
+            .iter()
+            .map(|lit| self.breaks[lit.index()])
+            .min()
+            .unwrap();
+
+        if min_break > 0 && rng.random_bool(noise) {
+            return lits[rng.random_range(0..lits.len())].index();
+        }
+
+        let best: Vec<usize> = lits
+            .iter()
+            .map(|lit| lit.index())
+            .filter(|var| self.breaks[*var] == min_break)
+            .collect();
+
+        best[rng.random_range(0..best.len())]
+    }
+
+    fn pick_probsat(&self, clause: usize, cb: f64, rng: &mut impl Rng) -> usize {
+        let lits = &self.clauses[clause];
+        let scores: Vec<f64> = lits
+            .iter()
+            .map(|lit| (1.0 + self.breaks[lit.index()] as f64).powf(-cb))
+            .collect();
+
+        let total: f64 = scores.iter().sum();
+
+        // Large breaks raised to a large `cb` can underflow every score to zero
+        if total <= 0.0 {
+            return lits[rng.random_range(0..lits.len())].index();
+        }
+
+        let mut target = rng.random_range(0.0..total);
+
+        for (lit, score) in lits.iter().zip(&scores) {
+            if target < *score {
+                return lit.index();
+            }
+
+            target -= score;
+        }
+
+        lits[lits.len() - 1].index()
+    }
+}
@@ -0,0 +1,336 @@
+//! Lookahead
+//!
+//! Tentatively propagates both polarities of candidate variables to measure how much each simplifies the formula.
+//! Literals whose propagation fails are refuted, so their complements are necessary assignments.
+
+use std::collections::HashSet;
+
+use crate::{formula::clausal, Assignment, Formula, Lit, SolveResult, Var};
+
+/// Unit propagation over occurrence lists, with a trail that can be undone to any earlier point.
+#[derive(Debug, Clone)]
+pub(crate) struct Propagator {
+    clauses: Vec<Vec<Lit>>,
+    /// Clause indices containing each literal, indexed by `Lit::code`.
+    occurrences: Vec<Vec<usize>>,
+    values: Vec<Option<bool>>,
+    trail: Vec<Lit>,
+    /// `false` once a clause is falsified at the top level.
+    ok: bool,
+}
+
+impl Propagator {
+    pub fn new(formula: &Formula) -> Propagator {
+        let formula = &*clausal(formula);
+        let var_count = formula.max_var().map_or(0, |var| var.index() + 1);
+
+        let mut propagator = Propagator {
+            clauses: Vec::new(),
+            occurrences: vec![Vec::new(); var_count * 2],
+            values: vec![None; var_count],
+            trail: Vec::new(),
+            ok: true,
+        };
+
+        let mut units = Vec::new();
+
+        for clause in formula.clauses() {
+            let mut lits = clause.literals();
+            lits.sort();
+            lits.dedup();
+
+            if lits.windows(2).any(|pair| pair[0] == !pair[1]) {
+                continue;
+            }
+
+            match lits.as_slice() {
+                [] => propagator.ok = false,
+                [unit] => units.push(*unit),
+                _ => {
+                    for lit in &lits {
+                        propagator.occurrences[lit.code()].push(propagator.clauses.len());
+                    }
+
+                    propagator.clauses.push(lits);
+                }
+            }
+        }
+
+        for unit in units {
+            if propagator.ok && !propagator.assign(unit) {
+                propagator.ok = false;
+            }
+        }
+
+        propagator
+    }
+
+    /// Returns `false` if the formula is falsified without any assignments.
+    pub fn is_ok(&self) -> bool {
+        self.ok
+    }
+
+    pub fn value(&self, lit: Lit) -> Option<bool> {
+        self.values[lit.index()].map(|value| lit.evaluate(value))
+    }
+
+    /// The number of literals assigned so far, to pass to `undo`.
+    pub fn mark(&self) -> usize {
+        self.trail.len()
+    }
+
+    /// Unassigns every literal assigned since `mark`.
+    pub fn undo(&mut self, mark: usize) {
+        for lit in self.trail.drain(mark..) {
+            self.values[lit.index()] = None;
+        }
+    }
+
+    /// The literals assigned since `mark`.
+    pub fn assigned_since(&self, mark: usize) -> &[Lit] {
+        &self.trail[mark..]
+    }
+
+    /// Assigns `lit` and propagates, returning `false` on conflict.
+    ///
+    /// On conflict the assignments are kept, so callers should `undo` to an earlier mark.
+    pub fn assign(&mut self, lit: Lit) -> bool {
+        match self.value(lit) {
+            Some(value) => return value,
+            None => self.enqueue(lit),
+        }
+
+        let mut head = self.trail.len() - 1;
+
+        while head < self.trail.len() {
+            let false_lit = !self.trail[head];
+            head += 1;
+
+            for index in 0..self.occurrences[false_lit.code()].len() {
+                let clause = self.occurrences[false_lit.code()][index];
+                let mut unassigned = None;
+                let mut unassigned_count = 0;
+                let mut satisfied = false;
+
+                for lit in &self.clauses[clause] {
+                    match self.value(*lit) {
+                        Some(true) => {
+                            satisfied = true;
+                            break;
+                        }
+                        Some(false) => {}
+                        None => {
+                            unassigned = Some(*lit);
+                            unassigned_count += 1;
+                        }
+                    }
+                }
+
+                if satisfied {
+                    continue;
+                }
+
+                match unassigned_count {
+                    0 => return false,
+                    1 => self.enqueue(unassigned.unwrap()),
+                    _ => {}
+                }
+            }
+        }
+
+        true
+    }
+
+    fn enqueue(&mut self, lit: Lit) {
+        self.values[lit.index()] = Some(lit.polarity());
+        self.trail.push(lit);
+    }
+
+    /// The number of clauses shortened, but not satisfied, by the literals assigned since `mark`.
+    pub fn reductions_since(&self, mark: usize) -> usize {
+        self.trail[mark..]
+            .iter()
+            .flat_map(|lit| &self.occurrences[(!*lit).code()])
+            .filter(|clause| {
+                !self.clauses[**clause]
+                    .iter()
+                    .any(|lit| self.value(*lit) == Some(true))
+            })
+            .count()
+    }
+
+    /// Returns `true` if every clause has a true literal.
+    pub fn is_satisfied(&self) -> bool {
+        self.clauses
+            .iter()
+            .all(|lits| lits.iter().any(|lit| self.value(*lit) == Some(true)))
+    }
+
+    /// Unassigned variables in clauses not yet satisfied, with their number of occurrences in those clauses.
+    pub fn candidates(&self) -> Vec<(Var, usize)> {
+        let mut counts = vec![0; self.values.len()];
+
+        for lits in &self.clauses {
+            if lits.iter().any(|lit| self.value(*lit) == Some(true)) {
+                continue;
+            }
+
+            for lit in lits {
+                if self.values[lit.index()].is_none() {
+                    counts[lit.index()] += 1;
+                }
+            }
+        }
+
+        counts
+            .into_iter()
+            .enumerate()
+            .filter(|(_, count)| *count > 0)
+            .map(|(index, count)| (Var::from_index(index).unwrap(), count))
+            .collect()
+    }
+}
+
+/// The outcome of looking ahead on a set of candidate variables.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Lookahead {
+    /// Every candidate was tried; `Some` holds the best literal to branch on first, if any variables remain unassigned.
+    Branch(Option<Lit>),
+    /// Propagating necessary assignments falsified the formula.
+    Conflict,
+}
+
+/// Looks ahead on up to `limit` of the most frequent unassigned variables.
+///
+/// Failed literals and necessary assignments found along the way are kept on the propagator's trail.
+/// A variable's score combines the number of clauses each of its polarities shortens without satisfying, favouring variables where both do.
+pub(crate) fn look_ahead(propagator: &mut Propagator, limit: usize) -> Lookahead {
+    let mut candidates = propagator.candidates();
+    candidates.sort_by_key(|(var, count)| (std::cmp::Reverse(*count), *var));
+    candidates.truncate(limit);
+
+    let mut best: Option<(Lit, usize)> = None;
+
+    for (var, _) in candidates {
+        if propagator.value(var.positive()).is_some() {
+            continue;
+        }
+
+        let mut reductions = [0; 2];
+        let mut implied: [HashSet<Lit>; 2] = Default::default();
+        let mut failed = None;
+
+        for (side, lit) in [var.positive(), var.negative()].into_iter().enumerate() {
+            let mark = propagator.mark();
+
+            if propagator.assign(lit) {
+                reductions[side] = propagator.reductions_since(mark);
+                implied[side] = propagator.assigned_since(mark).iter().copied().collect();
+            } else {
+                failed = Some(lit);
+            }
+
+            propagator.undo(mark);
+
+            if failed.is_some() {
+                break;
+            }
+        }
+
+        // A failed literal: its complement is necessary
+        if let Some(lit) = failed {
+            if !propagator.assign(!lit) {
+                return Lookahead::Conflict;
+            }
+
+            continue;
+        }
+
+        // Literals implied by both polarities are necessary
+        for lit in implied[0].intersection(&implied[1]) {
+            if !propagator.assign(*lit) {
+                return Lookahead::Conflict;
+            }
+        }
+
+        let [positive, negative] = reductions;
+        let score = 1024 * positive * negative + positive + negative;
+
+        // Branch first on the polarity that shortens fewer clauses, as it is more likely satisfiable
+        let first = if positive <= negative {
+            var.positive()
+        } else {
+            var.negative()
+        };
+
+        if best.is_none_or(|(_, best_score)| score > best_score) {
+            best = Some((first, score));
+        }
+    }
+
+    // Necessary assignments may have assigned the best variable
+    let branch = best
+        .map(|(lit, _)| lit)
+        .filter(|lit| propagator.value(*lit).is_none())
+        .or_else(|| {
+            propagator
+                .candidates()
+                .into_iter()
+                .max_by_key(|(_, count)| *count)
+                .map(|(var, _)| var.negative())
+        });
+
+    Lookahead::Branch(branch)
+}
+
+/// The number of variables looked ahead on at each node of `lookahead_solve`.
+const CANDIDATES: usize = 64;
+
+/// Solves `formula` with a lookahead DPLL search in the spirit of march.
+///
+/// At every node, candidate variables are propagated with both polarities to find failed literals and necessary assignments, and the search branches on the variable that most reduces the formula either way.
+/// This suits small, hard instances such as random k-SAT near the phase transition.
+pub fn lookahead_solve(formula: &Formula) -> SolveResult {
+    let mut propagator = Propagator::new(formula);
+
+    if !propagator.is_ok() || !search(&mut propagator) {
+        return SolveResult::Unsat;
+    }
+
+    let mut assignment = Assignment::new();
+
+    for var in formula.vars() {
+        assignment.set(var, propagator.value(var.positive()).unwrap_or(false));
+    }
+
+    SolveResult::Sat(assignment)
+}
+
+/// Searches below the current assignment, leaving a satisfying assignment on the trail if one is found.
+fn search(propagator: &mut Propagator) -> bool {
+    if propagator.is_satisfied() {
+        return true;
+    }
+
+    let mark = propagator.mark();
+
+    if let Lookahead::Branch(branch) = look_ahead(propagator, CANDIDATES) {
+        let Some(lit) = branch else {
+            return propagator.is_satisfied();
+        };
+
+        for lit in [lit, !lit] {
+            let branch_mark = propagator.mark();
+
+            if propagator.assign(lit) && search(propagator) {
+                return true;
+            }
+
+            propagator.undo(branch_mark);
+        }
+    }
+
+    propagator.undo(mark);
+
+    false
+}
@@ -1,15 +1,42 @@
-use std::time::Instant;
+use std::{collections::HashMap, time::Instant};
 
-use hydra;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use varisat::ExtendFormula;
 
 fn main() {
+    run_sampling();
+
     (0..16).into_par_iter().for_each(|_| {
         let now = Instant::now();
 
+        // Each approximate count enumerates many cells, so it runs once per thread
+        run_approx();
+
         for _ in 0..256 {
             run_random();
+            run_maxsat();
+            run_local_search();
+            run_portfolio();
+            run_cubes();
+            run_preprocess();
+            run_simplify();
+            run_equivalent_literals();
+            run_inprocessing();
+            run_2sat();
+            run_horn();
+            run_xor();
+            run_xor_paths();
+            run_cardinality();
+            run_cardinality_pairs();
+            run_count();
+            run_components();
+            run_backbone();
+            run_mus();
+            run_mcs();
+            run_minimal();
+            run_equivalence();
+            run_symmetry();
+            run_qbf();
         }
 
         let elapsed = now.elapsed();
@@ -32,12 +59,1351 @@ fn run_random() {
             panic!("disagreement: hydra {}, varisat: {}", sat, !sat);
         }
 
+        let cdcl = hydra::solve_with(&formula, &hydra::Engine::Cdcl(Default::default()));
+
+        if cdcl.is_sat() != sat {
+            panic!("disagreement: cdcl {}, dpll: {}", cdcl.is_sat(), sat);
+        }
+
+        let lookahead = hydra::solve_with(&formula, &hydra::Engine::Lookahead);
+
+        if lookahead.is_sat() != sat {
+            panic!(
+                "disagreement: lookahead {}, dpll: {}",
+                lookahead.is_sat(),
+                sat
+            );
+        }
+
         if !sat {
             break;
         }
     }
 }
 
+fn run_maxsat() {
+    let mut instance = hydra::WeightedFormula::new();
+
+    for _ in 0..rand::random_range(0..8) {
+        instance.add_hard(hydra::Clause::random(3, 0..8).unwrap());
+    }
+
+    for _ in 0..rand::random_range(1..12) {
+        let len = rand::random_range(1..=3);
+        let mut lits = hydra::Clause::random(len, 0..8).unwrap().literals();
+
+        // Repeated literals and zero weights are both valid input
+        if rand::random_bool(0.2) {
+            lits.push(lits[0]);
+        }
+
+        instance.add_soft(lits.into(), rand::random_range(0..5));
+    }
+
+    let instance = hydra::parse_wcnf(&hydra::write_wcnf(&instance)).unwrap();
+
+    // The optimum over every assignment of the eight variables
+    let vars: Vec<hydra::Var> = (0..8).map(|i| hydra::Var::from_index(i).unwrap()).collect();
+    let optimum = (0..1u32 << vars.len())
+        .filter_map(|values| {
+            let mut assignment = hydra::Assignment::new();
+
+            for (i, var) in vars.iter().enumerate() {
+                assignment.set(*var, values >> i & 1 == 1);
+            }
+
+            (instance.hard().evaluate(&assignment) == Some(true))
+                .then(|| instance.cost(&assignment))
+        })
+        .min();
+
+    for algorithm in [
+        hydra::MaxSatAlgorithm::LinearSatUnsat,
+        hydra::MaxSatAlgorithm::FuMalik,
+    ] {
+        let solution = hydra::solve_maxsat(&instance, algorithm);
+
+        if solution.as_ref().map(|solution| solution.cost()) != optimum {
+            panic!(
+                "disagreement: {:?} cost {:?}, brute force: {:?}",
+                algorithm,
+                solution.map(|solution| solution.cost()),
+                optimum
+            );
+        }
+
+        if let Some(solution) = solution {
+            let model = solution.model().completed(&vars);
+
+            if instance.hard().evaluate(&model) != Some(true) {
+                panic!("{:?} model violates a hard clause", algorithm);
+            }
+
+            if instance.cost(&model) != solution.cost() {
+                panic!("{:?} reported the wrong cost of its model", algorithm);
+            }
+        }
+    }
+}
+
+fn run_local_search() {
+    let mut formula = hydra::Formula::new();
+    let planted: Vec<bool> = (0..10).map(|_| rand::random_bool(0.5)).collect();
+
+    // Keeping only clauses satisfied by a planted assignment makes the formula satisfiable
+    while formula.clauses().len() < 40 {
+        let clause = hydra::Clause::random(3, 0..10).unwrap();
+
+        if clause
+            .literals()
+            .iter()
+            .any(|lit| lit.polarity() == planted[lit.index()])
+        {
+            formula.add_clause(clause);
+        }
+    }
+
+    // Nearly greedy settings can stall in a local minimum, so both keep some randomness
+    for algorithm in [
+        hydra::LocalSearchAlgorithm::WalkSat {
+            noise: rand::random_range(0.2..=1.0),
+        },
+        hydra::LocalSearchAlgorithm::ProbSat {
+            cb: rand::random_range(0.0..3.0),
+        },
+    ] {
+        let config = hydra::LocalSearchConfig {
+            algorithm,
+            max_flips: 100_000,
+            seed: rand::random(),
+            ..Default::default()
+        };
+
+        match hydra::local_search(&formula, &config) {
+            hydra::SolveResult::Sat(model) if formula.evaluate(&model) == Some(true) => {}
+            hydra::SolveResult::Sat(model) => {
+                panic!("{:?} model {:?} does not satisfy formula", algorithm, model)
+            }
+            result => panic!("{:?} gave {:?} on a satisfiable formula", algorithm, result),
+        }
+    }
+}
+
+fn run_portfolio() {
+    let mut formula = hydra::Formula::new();
+
+    // Near the satisfiability threshold, so both outcomes are common and workers learn clauses to share
+    for _ in 0..rand::random_range(70..100) {
+        formula.add_clause(hydra::Clause::random(3, 0..20).unwrap());
+    }
+
+    let config = hydra::PortfolioConfig {
+        share_max_len: [None, Some(2), Some(8)][rand::random_range(0..3)],
+        ..Default::default()
+    };
+
+    let result = hydra::solve_portfolio(&formula, &config);
+    let sat = varisat_sat(&formula);
+
+    match result {
+        hydra::SolveResult::Sat(model) if sat => {
+            if formula.evaluate(&model.completed(&formula.vars())) != Some(true) {
+                panic!("portfolio model does not satisfy formula");
+            }
+        }
+        hydra::SolveResult::Unsat if !sat => {}
+        result => panic!("disagreement: portfolio {:?}, varisat: {}", result, sat),
+    }
+}
+
+fn run_cubes() {
+    let mut formula = hydra::Formula::new();
+
+    for _ in 0..rand::random_range(1..48) {
+        formula.add_clause(hydra::Clause::random(3, 0..10).unwrap());
+    }
+
+    // Parity constraints over six or more variables are encoded with auxiliary variables
+    for _ in 0..rand::random_range(0..3) {
+        let len = rand::random_range(1..=8);
+        formula.add_xor(hydra::Xor::new(
+            &hydra::Clause::random(len, 0..10).unwrap().literals(),
+        ));
+    }
+
+    let depth = rand::random_range(0..5);
+    let cubes = hydra::cubes(&formula, depth);
+
+    // Every model satisfies some cube
+    for model in hydra::models(&formula) {
+        if !cubes
+            .iter()
+            .any(|cube| cube.iter().all(|lit| model.evaluate(lit) == Some(true)))
+        {
+            panic!("model {:?} is in none of the cubes {:?}", model, cubes);
+        }
+    }
+
+    let sat = varisat_sat(&formula.to_cnf());
+    let result = hydra::cube_and_conquer(&formula, depth);
+
+    match result {
+        hydra::SolveResult::Sat(model) if sat => {
+            if formula.evaluate(&model.completed(&formula.vars())) != Some(true) {
+                panic!("cube and conquer model does not satisfy formula");
+            }
+        }
+        hydra::SolveResult::Unsat if !sat => {}
+        result => panic!(
+            "disagreement: cube and conquer {:?}, varisat: {}",
+            result, sat
+        ),
+    }
+
+    // The iCNF output is the formula as DIMACS clauses followed by one assumption line per cube
+    let icnf = hydra::write_icnf(&formula, &cubes);
+    let (assumptions, clauses): (Vec<&str>, Vec<&str>) = icnf
+        .lines()
+        .skip(1)
+        .partition(|line| line.starts_with("a "));
+
+    let written: Vec<Vec<hydra::Lit>> = assumptions
+        .iter()
+        .map(|line| hydra::parse_cnf(&line[2..]).unwrap().clauses()[0].literals())
+        .collect();
+
+    if written != cubes {
+        panic!("icnf cubes {:?} differ from {:?}", written, cubes);
+    }
+
+    let parsed = hydra::parse_cnf(&clauses.join("\n")).unwrap();
+
+    if format!("{:?}", parsed) != format!("{:?}", formula.to_cnf()) {
+        panic!("icnf clauses differ from {:?}", formula);
+    }
+
+    // Each cube is satisfiable in the written clauses exactly when it is in the formula
+    let mut solver = hydra::Solver::from_formula(&parsed, Default::default());
+
+    for cube in &cubes {
+        let mut restricted = formula.to_cnf();
+
+        for lit in cube {
+            restricted.add_clause(vec![*lit].into());
+        }
+
+        let expected = varisat_sat(&restricted);
+
+        match solver.solve_with_assumptions(cube) {
+            hydra::SolveResult::Sat(model) if expected => {
+                if cube.iter().any(|lit| model.evaluate(lit) != Some(true)) {
+                    panic!("icnf model {:?} does not satisfy cube {:?}", model, cube);
+                }
+            }
+            hydra::SolveResult::Unsat if !expected => {}
+            result => panic!(
+                "disagreement: icnf cube {:?} is {:?}, varisat: {}",
+                cube, result, expected
+            ),
+        }
+    }
+}
+
+fn run_preprocess() {
+    let mut formula = hydra::Formula::new();
+
+    for _ in 0..rand::random_range(1..40) {
+        let len = rand::random_range(1..=3);
+        formula.add_clause(hydra::Clause::random(len, 0..10).unwrap());
+    }
+
+    let passes = [
+        hydra::Pass::VariableElimination,
+        hydra::Pass::Subsumption,
+        hydra::Pass::EquivalentLiterals,
+    ];
+
+    // The default pipeline, and a random sequence of passes with variable elimination limits that rarely stop it
+    let configs = [
+        hydra::PreprocessConfig::default(),
+        hydra::PreprocessConfig {
+            passes: (0..rand::random_range(1..5))
+                .map(|_| passes[rand::random_range(0..passes.len())])
+                .collect(),
+            max_occurrences: 64,
+            max_resolvent_len: 64,
+        },
+    ];
+
+    let sat = varisat_sat(&formula);
+
+    for config in configs {
+        let preprocessed = hydra::preprocess(&formula, &config);
+
+        if varisat_sat(preprocessed.formula()) != sat {
+            panic!(
+                "{:?} changed satisfiability of {:?} to {:?}",
+                config.passes,
+                formula,
+                preprocessed.formula()
+            );
+        }
+
+        let result = hydra::solve_with_preprocessing(
+            &formula,
+            &config,
+            &hydra::Engine::Cdcl(Default::default()),
+        );
+
+        match result {
+            hydra::SolveResult::Sat(model) if sat => {
+                if formula.evaluate(&model) != Some(true) {
+                    panic!(
+                        "{:?} extended model {:?} does not satisfy {:?}",
+                        config.passes, model, formula
+                    );
+                }
+            }
+            hydra::SolveResult::Unsat if !sat => {}
+            result => panic!("disagreement: preprocessed {:?}, varisat: {}", result, sat),
+        }
+    }
+}
+
+fn run_simplify() {
+    let mut formula = hydra::Formula::new();
+
+    for _ in 0..rand::random_range(1..40) {
+        let len = rand::random_range(1..=4);
+        formula.add_clause(hydra::Clause::random(len, 0..8).unwrap());
+    }
+
+    let (simplified, stats) = hydra::simplify(&formula);
+
+    if simplified.clauses().len() + stats.removed_clauses != formula.clauses().len() {
+        panic!("{:?} does not account for every clause", stats);
+    }
+
+    // Each formula implies every clause of the other
+    for (a, b) in [(&formula, &simplified), (&simplified, &formula)] {
+        for clause in b.clauses() {
+            let mut negated = a.clone();
+
+            for lit in clause.literals() {
+                negated.add_clause(vec![!lit].into());
+            }
+
+            if varisat_sat(&negated) {
+                panic!("{:?} and {:?} differ on {:?}", formula, simplified, clause);
+            }
+        }
+    }
+
+    // Simplification stops early once it derives the empty clause
+    if simplified.clauses().iter().any(|clause| clause.is_empty()) {
+        return;
+    }
+
+    // Neither subsumption nor self-subsuming resolution applies to what is left
+    let sorted: Vec<Vec<hydra::Lit>> = simplified
+        .clauses()
+        .iter()
+        .map(|clause| {
+            let mut lits = clause.literals();
+            lits.sort();
+            lits.dedup();
+            lits
+        })
+        .collect();
+
+    for (i, a) in sorted.iter().enumerate() {
+        for (j, b) in sorted.iter().enumerate() {
+            let outside: Vec<&hydra::Lit> = a.iter().filter(|lit| !b.contains(lit)).collect();
+
+            if i != j && (outside.is_empty() || matches!(outside[..], [lit] if b.contains(&!*lit)))
+            {
+                panic!("{:?} can still simplify {:?}", a, b);
+            }
+        }
+    }
+}
+
+fn run_equivalent_literals() {
+    let mut formula = hydra::Formula::new();
+
+    // Mostly binary clauses, so that the implication graph has cycles
+    for _ in 0..rand::random_range(1..30) {
+        let len = if rand::random_bool(0.8) { 2 } else { 3 };
+        formula.add_clause(hydra::Clause::random(len, 0..8).unwrap());
+    }
+
+    let sat = varisat_sat(&formula);
+
+    for class in hydra::equivalent_literals(&formula) {
+        if class.windows(2).any(|pair| pair[0] == !pair[1]) {
+            if sat {
+                panic!(
+                    "{:?} has complementary class {:?} but is satisfiable",
+                    formula, class
+                );
+            }
+
+            continue;
+        }
+
+        // Every literal of a class is true exactly when the first one is
+        for lit in &class[1..] {
+            for (a, b) in [(class[0], *lit), (*lit, class[0])] {
+                let mut differ = formula.clone();
+                differ.add_clause(vec![a].into());
+                differ.add_clause(vec![!b].into());
+
+                if varisat_sat(&differ) {
+                    panic!("{:?} and {:?} are not equivalent in {:?}", a, b, formula);
+                }
+            }
+        }
+    }
+
+    let config = hydra::PreprocessConfig {
+        passes: vec![hydra::Pass::EquivalentLiterals],
+        ..Default::default()
+    };
+    let preprocessed = hydra::preprocess(&formula, &config);
+
+    if varisat_sat(preprocessed.formula()) != sat {
+        panic!(
+            "substitution changed satisfiability of {:?} to {:?}",
+            formula,
+            preprocessed.formula()
+        );
+    }
+
+    match hydra::solve_with_preprocessing(&formula, &config, &hydra::Engine::Dpll) {
+        hydra::SolveResult::Sat(model) if sat => {
+            if formula.evaluate(&model) != Some(true) {
+                panic!("extended model {:?} does not satisfy {:?}", model, formula);
+            }
+        }
+        hydra::SolveResult::Unsat if !sat => {}
+        result => panic!("disagreement: substituted {:?}, varisat: {}", result, sat),
+    }
+}
+
+fn run_inprocessing() {
+    let mut formula = hydra::Formula::new();
+
+    // Near the satisfiability threshold, so that search restarts often enough to inprocess
+    for _ in 0..rand::random_range(80..100) {
+        formula.add_clause(hydra::Clause::random(3, 0..20).unwrap());
+    }
+
+    // Inprocessing after every restart, with a restart after every conflict or two
+    let config = hydra::SolverConfig {
+        seed: rand::random(),
+        restarts: hydra::Restarts::Luby { unit: 1 },
+        inprocess_interval: Some(1),
+        ..Default::default()
+    };
+    let mut solver = hydra::Solver::from_formula(&formula, config);
+
+    for _ in 0..8 {
+        let assumptions = hydra::Clause::random(rand::random_range(0..4), 0..20)
+            .unwrap()
+            .literals();
+        let mut assumed = formula.clone();
+
+        for lit in &assumptions {
+            assumed.add_clause(vec![*lit].into());
+        }
+
+        let sat = varisat_sat(&assumed);
+
+        match solver.solve_with_assumptions(&assumptions) {
+            hydra::SolveResult::Sat(model) if sat => {
+                if assumed.evaluate(&model) != Some(true) {
+                    panic!(
+                        "inprocessing model {:?} does not satisfy {:?}",
+                        model, assumed
+                    );
+                }
+            }
+            hydra::SolveResult::Unsat if !sat => {
+                let failed = solver.failed_assumptions();
+
+                if failed.iter().any(|lit| !assumptions.contains(lit)) {
+                    panic!("failed assumptions {:?} not in {:?}", failed, assumptions);
+                }
+
+                let mut core = formula.clone();
+
+                for lit in failed {
+                    core.add_clause(vec![*lit].into());
+                }
+
+                if varisat_sat(&core) {
+                    panic!(
+                        "failed assumptions {:?} are consistent with {:?}",
+                        failed, formula
+                    );
+                }
+            }
+            result => panic!(
+                "disagreement: inprocessing {:?}, varisat: {}, stats {:?}",
+                result,
+                sat,
+                solver.stats()
+            ),
+        }
+    }
+}
+
+fn run_2sat() {
+    let mut formula = hydra::Formula::new();
+
+    for _ in 0..rand::random_range(1..32) {
+        let len = rand::random_range(1..=2);
+        formula.add_clause(hydra::Clause::random(len, 0..12).unwrap());
+    }
+
+    let solution = hydra::solve(&formula);
+    let sat = solution.is_some();
+
+    if sat != varisat_sat(&formula) {
+        panic!("disagreement: 2-sat {}, varisat: {}", sat, !sat);
+    }
+
+    if let Some(solution) = solution {
+        if formula.evaluate(&solution) != Some(true) {
+            panic!("2-sat solution does not satisfy formula");
+        }
+    }
+}
+
+fn run_horn() {
+    let mut formula = hydra::Formula::new();
+
+    for _ in 0..rand::random_range(1..32) {
+        let len = rand::random_range(1..=4);
+        let mut lits = hydra::Clause::random(len, 0..9).unwrap().literals();
+
+        // Keep at most one positive literal
+        for lit in lits.iter_mut().skip(1) {
+            if lit.is_positive() {
+                *lit = !*lit;
+            }
+        }
+
+        formula.add_clause(lits.into());
+    }
+
+    let solution = hydra::minimal_model(&formula);
+
+    if solution.is_some() != varisat_sat(&formula) {
+        panic!(
+            "disagreement: horn {}, varisat: {}",
+            solution.is_some(),
+            solution.is_none()
+        );
+    }
+
+    if let Some(solution) = solution {
+        if formula.evaluate(&solution) != Some(true) {
+            panic!("horn solution does not satisfy formula");
+        }
+
+        for model in hydra::models(&formula) {
+            if solution
+                .lits()
+                .iter()
+                .any(|lit| lit.is_positive() && model.evaluate(lit) != Some(true))
+            {
+                panic!("horn solution is not minimal");
+            }
+        }
+    }
+
+    // Complementing every literal gives a dual-Horn formula
+    let mut dual = hydra::Formula::new();
+
+    for clause in formula.clauses() {
+        let lits: Vec<hydra::Lit> = clause.literals().into_iter().map(|lit| !lit).collect();
+        dual.add_clause(lits.into());
+    }
+
+    if hydra::solve_dual_horn(&dual).is_some() != varisat_sat(&dual) {
+        panic!("disagreement: dual horn disagrees with varisat");
+    }
+}
+
+fn run_xor() {
+    let mut formula = hydra::Formula::new();
+
+    for _ in 0..rand::random_range(0..16) {
+        formula.add_clause(hydra::Clause::random(3, 0..12).unwrap());
+    }
+
+    for _ in 0..rand::random_range(1..12) {
+        let len = rand::random_range(1..=5);
+        let lits = hydra::Clause::random(len, 0..12).unwrap().literals();
+        formula.add_xor(hydra::Xor::new(&lits));
+    }
+
+    let solution = hydra::solve(&formula);
+    let sat = solution.is_some();
+
+    if sat != varisat_sat(&formula.to_cnf()) {
+        panic!("disagreement: xor {}, varisat: {}", sat, !sat);
+    }
+
+    if let Some(solution) = solution {
+        if formula.evaluate(&solution) != Some(true) {
+            panic!("xor solution does not satisfy formula");
+        }
+    }
+}
+
+fn run_xor_paths() {
+    let mut formula = hydra::Formula::new();
+    let mut text = String::new();
+
+    for _ in 0..rand::random_range(0..8) {
+        formula.add_clause(hydra::Clause::random(3, 0..10).unwrap());
+    }
+
+    // Constraints over six or more variables are encoded with auxiliary variables rather than expanded
+    for _ in 0..rand::random_range(1..4) {
+        let len = rand::random_range(1..=10);
+        let lits = hydra::Clause::random(len, 0..10).unwrap().literals();
+        let line: Vec<String> = lits.iter().map(|lit| lit.to_dimacs().to_string()).collect();
+        text.push_str(&format!("x{} 0\n", line.join(" ")));
+        formula.add_xor(hydra::Xor::new(&lits));
+    }
+
+    // An x-line requires an odd number of its literals to be true
+    let parsed = hydra::parse_cnf(&text).unwrap();
+    let vars: Vec<hydra::Var> = (0..10)
+        .map(|index| hydra::Var::from_index(index).unwrap())
+        .collect();
+    let assignments = (0..1u32 << vars.len()).map(|values| {
+        let mut assignment = hydra::Assignment::new();
+
+        for (i, var) in vars.iter().enumerate() {
+            assignment.set(*var, values >> i & 1 == 1);
+        }
+
+        assignment
+    });
+
+    for assignment in assignments.clone() {
+        for (xor, line) in parsed.xors().iter().zip(text.lines()) {
+            let odd = hydra::parse_cnf(&line[1..]).unwrap().clauses()[0]
+                .literals()
+                .iter()
+                .filter(|lit| assignment.evaluate(lit) == Some(true))
+                .count()
+                % 2
+                == 1;
+
+            if xor.evaluate(&assignment) != Some(odd) {
+                panic!(
+                    "{:?} parsed from {:?} is wrong on {:?}",
+                    xor, line, assignment
+                );
+            }
+        }
+    }
+
+    if format!(
+        "{:?}",
+        hydra::parse_cnf(&hydra::write_cnf(&formula)).unwrap()
+    ) != format!("{:?}", formula)
+    {
+        panic!("{:?} does not round trip", formula);
+    }
+
+    let sat = varisat_sat(&formula.to_cnf());
+    let formula_vars = formula.vars();
+    let outside = |model: &hydra::Assignment| {
+        model
+            .vec()
+            .iter()
+            .any(|(var, _)| !formula_vars.contains(var) && var.index() != 20)
+    };
+
+    // An assumption outside the formula must not clash with the auxiliary variables
+    let assumption = hydra::Var::from_index(20).unwrap().positive();
+
+    match hydra::solve_with_assumptions(&formula, &[assumption]) {
+        Some(model) if sat => {
+            if formula.evaluate(&model) != Some(true) || model.evaluate(&assumption) != Some(true) {
+                panic!("dpll model {:?} does not satisfy {:?}", model, formula);
+            }
+
+            if outside(&model) {
+                panic!("dpll model {:?} assigns auxiliary variables", model);
+            }
+        }
+        None if !sat => {}
+        result => panic!("disagreement: dpll {:?}, varisat: {}", result, sat),
+    }
+
+    let expected = assignments
+        .filter(|assignment| formula.evaluate(assignment) == Some(true))
+        .count() as u64;
+    let count = hydra::count_projected_models(&formula, &vars);
+
+    if count != hydra::Count::from(expected) {
+        panic!(
+            "counted {} models of {:?}, expected {}",
+            count, formula, expected
+        );
+    }
+
+    let models: Vec<hydra::Assignment> = hydra::models(&formula).collect();
+    let distinct: std::collections::HashSet<Vec<hydra::Lit>> =
+        models.iter().map(|model| model.lits()).collect();
+
+    if hydra::count_models(&formula) != hydra::Count::from(models.len() as u64)
+        || distinct.len() != models.len()
+    {
+        panic!("enumerated {} models of {:?}", models.len(), formula);
+    }
+
+    for model in &models {
+        if formula.evaluate(model) != Some(true) || outside(model) {
+            panic!("enumerated {:?}, not a model of {:?}", model, formula);
+        }
+    }
+}
+
+fn run_cardinality() {
+    let mut formula = hydra::Formula::new();
+
+    for _ in 0..rand::random_range(0..16) {
+        formula.add_clause(hydra::Clause::random(3, 0..12).unwrap());
+    }
+
+    for _ in 0..rand::random_range(1..6) {
+        let len = rand::random_range(2..=8);
+        let lits = hydra::Clause::random(len, 0..12).unwrap().literals();
+        let bound = rand::random_range(0..=len);
+
+        if rand::random_bool(0.5) {
+            formula.add_cardinality(hydra::Cardinality::at_most(&lits, bound));
+        } else {
+            formula.add_cardinality(hydra::Cardinality::at_least(&lits, bound));
+        }
+    }
+
+    let solution = hydra::solve(&formula);
+    let sat = solution.is_some();
+
+    if sat != varisat_sat(&formula.to_cnf()) {
+        panic!("disagreement: cardinality {}, varisat: {}", sat, !sat);
+    }
+
+    if let Some(solution) = solution {
+        if formula.evaluate(&solution) != Some(true) {
+            panic!("cardinality solution does not satisfy formula");
+        }
+    }
+}
+
+fn run_cardinality_pairs() {
+    let vars: Vec<hydra::Var> = (0..8)
+        .map(|index| hydra::Var::from_index(index).unwrap())
+        .collect();
+    let assignments: Vec<hydra::Assignment> = (0..1u32 << vars.len())
+        .map(|values| {
+            let mut assignment = hydra::Assignment::new();
+
+            for (i, var) in vars.iter().enumerate() {
+                assignment.set(*var, values >> i & 1 == 1);
+            }
+
+            assignment
+        })
+        .collect();
+
+    // Short clauses, so that some literals are true at the top level before the constraints are added
+    let mut clauses = hydra::Formula::new();
+
+    for _ in 0..rand::random_range(0..6) {
+        let len = rand::random_range(1..=3);
+        clauses.add_clause(hydra::Clause::random(len, 0..8).unwrap());
+    }
+
+    let mut formula = clauses.clone();
+
+    for _ in 0..rand::random_range(1..4) {
+        // Literals drawn independently, so that constraints often repeat a literal or contain its complement
+        let lits: Vec<hydra::Lit> = (0..rand::random_range(1..=10))
+            .map(|_| {
+                hydra::Lit::from_index(rand::random_range(0..8), rand::random_bool(0.5)).unwrap()
+            })
+            .collect();
+        let mut distinct = lits.clone();
+        distinct.sort();
+        distinct.dedup();
+
+        let bound = rand::random_range(0..=distinct.len());
+        let at_most = rand::random_bool(0.5);
+        let cardinality = if at_most {
+            hydra::Cardinality::at_most(&lits, bound)
+        } else {
+            hydra::Cardinality::at_least(&lits, bound)
+        };
+
+        // Each distinct literal counts once
+        for assignment in &assignments {
+            let trues = distinct
+                .iter()
+                .filter(|lit| assignment.evaluate(lit) == Some(true))
+                .count();
+            let expected = if at_most {
+                trues <= bound
+            } else {
+                trues >= bound
+            };
+
+            if cardinality.evaluate(assignment) != Some(expected) {
+                panic!(
+                    "{:?} from {:?} with bound {} is wrong on {:?}",
+                    cardinality, lits, bound, assignment
+                );
+            }
+        }
+
+        formula.add_cardinality(cardinality);
+    }
+
+    let expected = assignments
+        .iter()
+        .filter(|assignment| formula.evaluate(assignment) == Some(true))
+        .count() as u64;
+    let sat = expected > 0;
+
+    if varisat_sat(&formula.to_cnf()) != sat {
+        panic!(
+            "disagreement: encoding of {:?} is {}, expected {}",
+            formula, !sat, sat
+        );
+    }
+
+    // Constraints added with the clauses, and added to a solver that has already solved
+    let mut incremental = hydra::Solver::from_formula(&clauses, Default::default());
+    incremental.solve();
+
+    for cardinality in formula.cardinalities() {
+        incremental.add_cardinality(cardinality);
+    }
+
+    for mut solver in [
+        hydra::Solver::from_formula(&formula, Default::default()),
+        incremental,
+    ] {
+        match solver.solve() {
+            hydra::SolveResult::Sat(model) if sat => {
+                if formula.evaluate(&model.completed(&vars)) != Some(true) {
+                    panic!(
+                        "cardinality model {:?} does not satisfy {:?}",
+                        model, formula
+                    );
+                }
+            }
+            hydra::SolveResult::Unsat if !sat => {}
+            result => panic!("disagreement: cardinality {:?}, expected {}", result, sat),
+        }
+    }
+
+    let count = hydra::count_projected_models(&formula, &vars);
+
+    if count != hydra::Count::from(expected) {
+        panic!(
+            "counted {} models of {:?}, expected {}",
+            count, formula, expected
+        );
+    }
+
+    // Written as clauses, whose auxiliary variables follow and are defined by those of the formula
+    let written = hydra::write_cnf(&formula);
+    let formula_vars = formula.vars();
+    let count = hydra::count_projected_models(&hydra::parse_cnf(&written).unwrap(), &formula_vars);
+    let expected = hydra::count_projected_models(&formula, &formula_vars);
+
+    if count != expected {
+        panic!(
+            "counted {} models of {:?} written as {:?}, expected {}",
+            count, formula, written, expected
+        );
+    }
+}
+
+fn run_components() {
+    let mut formula = hydra::Formula::new();
+
+    // Clauses over two disjoint ranges of variables give at least two components
+    for _ in 0..rand::random_range(1..24) {
+        let vars = if rand::random_bool(0.5) { 0..6 } else { 6..12 };
+        formula.add_clause(hydra::Clause::random(3, vars).unwrap());
+    }
+
+    let components = formula.components();
+    let constraints: usize = components.iter().map(|c| c.clauses().len()).sum();
+
+    if constraints != formula.clauses().len() {
+        panic!("components lost clauses: {:?}", components);
+    }
+
+    for (i, a) in components.iter().enumerate() {
+        for b in &components[i + 1..] {
+            if a.vars().iter().any(|var| b.vars().contains(var)) {
+                panic!("components share a variable: {:?}, {:?}", a, b);
+            }
+        }
+    }
+
+    let solution = hydra::solve(&formula);
+    let sat = solution.is_some();
+
+    if sat != varisat_sat(&formula) {
+        panic!("disagreement: components {}, varisat: {}", sat, !sat);
+    }
+
+    if let Some(solution) = solution {
+        if formula.evaluate(&solution) != Some(true) {
+            panic!("component solution does not satisfy formula");
+        }
+    }
+}
+
+fn run_backbone() {
+    let mut formula = hydra::Formula::new();
+
+    for _ in 0..rand::random_range(1..32) {
+        let len = rand::random_range(1..=3);
+        formula.add_clause(hydra::Clause::random(len, 0..10).unwrap());
+    }
+
+    let Some(backbone) = hydra::backbone(&formula, &Default::default()) else {
+        if varisat_sat(&formula) {
+            panic!("backbone found no model of a satisfiable formula");
+        }
+
+        return;
+    };
+
+    // Each backbone literal is implied, and each free variable can take either value
+    for lit in backbone.lits() {
+        let mut negated = formula.clone();
+        negated.add_clause(vec![!*lit].into());
+
+        if varisat_sat(&negated) {
+            panic!("{:?} is not in the backbone of {:?}", lit, formula);
+        }
+    }
+
+    for var in backbone.free() {
+        for lit in [var.positive(), var.negative()] {
+            let mut fixed = formula.clone();
+            fixed.add_clause(vec![lit].into());
+
+            if !varisat_sat(&fixed) {
+                panic!("{:?} is not free in {:?}", var, formula);
+            }
+        }
+    }
+}
+
+fn run_mus() {
+    let mut formula = hydra::Formula::new();
+
+    while varisat_sat(&formula) {
+        let len = rand::random_range(1..=3);
+        formula.add_clause(hydra::Clause::random(len, 0..8).unwrap());
+    }
+
+    let subset = |indices: &[usize]| {
+        let mut subset = hydra::Formula::new();
+
+        for index in indices {
+            subset.add_clause(formula.clauses()[*index].clone());
+        }
+
+        subset
+    };
+
+    for algorithm in [
+        hydra::MusAlgorithm::Deletion,
+        hydra::MusAlgorithm::QuickXplain,
+    ] {
+        let config = hydra::MusConfig {
+            algorithm,
+            ..Default::default()
+        };
+        let mus = hydra::mus(&formula, &config).expect("mus of an unsatisfiable formula");
+
+        if varisat_sat(&subset(&mus)) {
+            panic!("{:?} mus {:?} is satisfiable", algorithm, mus);
+        }
+
+        for i in 0..mus.len() {
+            let mut smaller = mus.clone();
+            smaller.remove(i);
+
+            if !varisat_sat(&subset(&smaller)) {
+                panic!("{:?} mus {:?} is not minimal", algorithm, mus);
+            }
+        }
+    }
+}
+
+fn run_mcs() {
+    let mut formula = hydra::Formula::new();
+
+    for _ in 0..rand::random_range(1..16) {
+        let len = rand::random_range(1..=2);
+        formula.add_clause(hydra::Clause::random(len, 0..5).unwrap());
+    }
+
+    let without = |removed: &[usize]| {
+        let mut kept = hydra::Formula::new();
+
+        for (i, clause) in formula.clauses().iter().enumerate() {
+            if !removed.contains(&i) {
+                kept.add_clause(clause.clone());
+            }
+        }
+
+        kept
+    };
+
+    let mcses: Vec<Vec<usize>> = hydra::mcses(&formula).collect();
+
+    for mcs in &mcses {
+        if !varisat_sat(&without(mcs)) {
+            panic!("removing {:?} does not correct {:?}", mcs, formula);
+        }
+
+        for i in 0..mcs.len() {
+            let mut smaller = mcs.clone();
+            smaller.remove(i);
+
+            if varisat_sat(&without(&smaller)) {
+                panic!("correction set {:?} is not minimal", mcs);
+            }
+        }
+    }
+
+    // Minimal correction sets are the complements of the maximal satisfiable subsets
+    let msses = hydra::marco(&formula, &Default::default())
+        .filter(|subset| matches!(subset, hydra::MarcoSubset::Mss(_)))
+        .count();
+
+    if msses != mcses.len() {
+        panic!("disagreement: {} mcses, marco {} msses", mcses.len(), msses);
+    }
+}
+
+fn run_minimal() {
+    let mut formula = hydra::Formula::new();
+
+    for _ in 0..rand::random_range(1..24) {
+        formula.add_clause(hydra::Clause::random(3, 0..9).unwrap());
+    }
+
+    let Some(model) = hydra::cardinality_minimal_model(&formula) else {
+        if varisat_sat(&formula) {
+            panic!("no minimal model of a satisfiable formula");
+        }
+
+        return;
+    };
+
+    let shrunk = model.shrink(&formula);
+
+    if formula.evaluate(&shrunk) != Some(true) {
+        panic!("shrunk model {:?} does not satisfy formula", shrunk);
+    }
+
+    // No model has fewer true variables
+    let trues = model.lits().iter().filter(|lit| lit.is_positive()).count();
+    let positive: Vec<hydra::Lit> = formula.vars().iter().map(|var| var.positive()).collect();
+
+    if trues > 0 {
+        let mut smaller = formula.clone();
+        smaller.add_cardinality(hydra::Cardinality::at_most(&positive, trues - 1));
+
+        if varisat_sat(&smaller.to_cnf()) {
+            panic!("{:?} is not a minimum model of {:?}", model, formula);
+        }
+    }
+
+    let subset = hydra::subset_minimal_model(&formula).unwrap();
+
+    if formula.evaluate(&subset) != Some(true) {
+        panic!("subset minimal model does not satisfy formula");
+    }
+}
+
+fn run_equivalence() {
+    let mut formula = hydra::Formula::new();
+
+    for _ in 0..rand::random_range(0..12) {
+        formula.add_clause(hydra::Clause::random(3, 0..8).unwrap());
+    }
+
+    let lits = hydra::Clause::random(4, 0..8).unwrap().literals();
+
+    // Native constraints are equivalent to their clause encodings on their own variables, and strengthen the formula
+    let mut native = formula.clone();
+    native.add_cardinality(hydra::Cardinality::at_most(&lits, rand::random_range(0..4)));
+
+    if let Err(counterexample) = native.equivalent_projected(&native.to_cnf(), &native.vars()) {
+        panic!("encoding differs on {:?}", counterexample);
+    }
+
+    if let Err(counterexample) = native.entails(&formula) {
+        panic!(
+            "{:?} does not entail {:?} on {:?}",
+            native, formula, counterexample
+        );
+    }
+
+    // Defining an auxiliary variable as a disjunction only changes the formula outside the original variables
+    let aux = hydra::Var::from_index(8).unwrap().positive();
+    let mut defined = formula.clone();
+    defined.add_clause(vec![!aux, lits[0], lits[1]].into());
+    defined.add_clause(vec![aux, !lits[0]].into());
+    defined.add_clause(vec![aux, !lits[1]].into());
+
+    let mut vars = formula.vars();
+    vars.extend(lits.iter().map(|lit| lit.var()));
+
+    if let Err(counterexample) = defined.equivalent_projected(&formula, &vars) {
+        panic!("definition differs on {:?}", counterexample);
+    }
+
+    if varisat_sat(&formula) && defined.equivalent(&formula).is_ok() {
+        panic!("definition does not constrain {:?}", aux);
+    }
+}
+
+fn run_symmetry() {
+    let mut formula = hydra::Formula::new();
+
+    // Clauses closed under swapping variables 1 and 2 give at least one symmetry
+    for _ in 0..rand::random_range(1..12) {
+        let clause = hydra::Clause::random(3, 0..6).unwrap();
+        let swapped: Vec<hydra::Lit> = clause
+            .literals()
+            .into_iter()
+            .map(|lit| match lit.index() {
+                0 | 1 => hydra::Lit::from_index(1 - lit.index(), lit.polarity()).unwrap(),
+                _ => lit,
+            })
+            .collect();
+
+        formula.add_clause(clause);
+        formula.add_clause(swapped.into());
+    }
+
+    let swaps = formula.vars().contains(&hydra::Var::from_index(0).unwrap());
+
+    if swaps && hydra::symmetries(&formula).is_empty() {
+        panic!("no symmetries found in {:?}", formula);
+    }
+
+    let broken = hydra::break_symmetries(&formula);
+    let sat = varisat_sat(&formula);
+
+    if varisat_sat(&broken) != sat {
+        panic!("disagreement: broken {}, original: {}", !sat, sat);
+    }
+
+    if let Some(solution) = hydra::solve(&broken) {
+        if formula.evaluate(&solution.completed(&formula.vars())) != Some(true) {
+            panic!("model of broken formula does not satisfy {:?}", formula);
+        }
+    }
+}
+
+fn run_qbf() {
+    let mut matrix = hydra::Formula::new();
+
+    for _ in 0..rand::random_range(1..16) {
+        matrix.add_clause(hydra::Clause::random(3, 0..8).unwrap());
+    }
+
+    // exists 1..4 forall 5..6 exists 7..8
+    let vars: Vec<hydra::Var> = (0..8).map(|i| hydra::Var::from_index(i).unwrap()).collect();
+    let mut qbf = hydra::Qbf::new(matrix.clone());
+    qbf.add_block(hydra::Quantifier::Exists, &vars[..4]);
+    qbf.add_block(hydra::Quantifier::Forall, &vars[4..6]);
+    qbf.add_block(hydra::Quantifier::Exists, &vars[6..]);
+
+    let result = hydra::solve_qbf(&hydra::parse_qdimacs(&hydra::write_qdimacs(&qbf)).unwrap());
+
+    // Expanding the universal variables gives a copy of the innermost block for each of their values
+    let mut expanded = hydra::Formula::new();
+
+    for values in 0..4 {
+        for clause in matrix.clauses() {
+            let mut lits = Vec::new();
+            let mut satisfied = false;
+
+            for lit in clause.literals() {
+                match lit.index() {
+                    4 | 5 => satisfied |= lit.polarity() == (values >> (lit.index() - 4) & 1 == 1),
+                    6 | 7 => lits.push(
+                        hydra::Lit::from_index(lit.index() + 4 * values, lit.polarity()).unwrap(),
+                    ),
+                    _ => lits.push(lit),
+                }
+            }
+
+            if !satisfied {
+                expanded.add_clause(lits.into());
+            }
+        }
+    }
+
+    if result.is_true() != varisat_sat(&expanded) {
+        panic!(
+            "disagreement: qbf {}, expansion: {}",
+            result.is_true(),
+            !result.is_true()
+        );
+    }
+
+    if let Some(certificate) = result.certificate() {
+        for lit in certificate.lits() {
+            expanded.add_clause(vec![lit].into());
+        }
+
+        if !varisat_sat(&expanded) {
+            panic!("certificate {:?} does not win", certificate);
+        }
+    }
+}
+
+fn run_count() {
+    let mut formula = hydra::Formula::new();
+
+    for _ in 0..rand::random_range(0..24) {
+        formula.add_clause(hydra::Clause::random(3, 0..9).unwrap());
+    }
+
+    let count = hydra::count_models(&formula);
+    let enumerated = hydra::models(&formula).count() as u64;
+
+    if count != hydra::Count::from(enumerated) {
+        panic!(
+            "disagreement: counted {}, enumerated: {}",
+            count, enumerated
+        );
+    }
+
+    let projection: Vec<hydra::Var> = (1..=5).map(hydra::Var::from).collect();
+    let count = hydra::count_projected_models(&formula, &projection);
+    let enumerated = hydra::projected_models(&formula, &projection).count() as u64;
+
+    if count != hydra::Count::from(enumerated) {
+        panic!(
+            "disagreement: projected counted {}, enumerated: {}",
+            count, enumerated
+        );
+    }
+}
+
+fn run_approx() {
+    let mut formula = hydra::Formula::new();
+
+    // Few clauses over many variables leave more models than one cell holds
+    for _ in 0..rand::random_range(0..12) {
+        formula.add_clause(hydra::Clause::random(3, 0..11).unwrap());
+    }
+
+    let epsilon = 0.8;
+    let count = hydra::count_models(&formula).to_f64();
+    let estimate = hydra::approx_count_models(&formula, epsilon, 0.05, rand::random()).to_f64();
+
+    if estimate > count * (1.0 + epsilon) || estimate < count / (1.0 + epsilon) {
+        panic!(
+            "approximate count {} is not within {} of {}",
+            estimate, epsilon, count
+        );
+    }
+}
+
+/// Checks that hashed samples of a formula with more models than fit in one cell are close to uniform.
+fn run_sampling() {
+    // Enough models for the statistic to be meaningful, few enough to draw each many times
+    let (formula, models) = loop {
+        let mut formula = hydra::Formula::new();
+
+        while hydra::count_models(&formula) > hydra::Count::from(120) || formula.clauses().len() < 4
+        {
+            formula.add_clause(hydra::Clause::random(3, 0..8).unwrap());
+        }
+
+        let models = hydra::count_models(&formula).to_u64().unwrap() as usize;
+
+        if models >= 60 {
+            break (formula, models);
+        }
+    };
+
+    let draws = models * 25;
+
+    // Wilson-Hilferty approximation of the 99.9th percentile of the chi-squared distribution
+    let df = (models - 1) as f64;
+    let critical = df * (1.0 - 2.0 / (9.0 * df) + 3.09 * (2.0 / (9.0 * df)).sqrt()).powi(3);
+
+    // A uniform sampler exceeds the percentile on one seed in a thousand, so the check fails only if three independent seeds all do
+    let mut statistics = Vec::new();
+
+    for seed in 0..3 {
+        let mut frequencies: HashMap<Vec<hydra::Lit>, usize> = HashMap::new();
+
+        for sample in hydra::samples(&formula, hydra::SamplingStrategy::Hashing, seed).take(draws) {
+            if formula.evaluate(&sample) != Some(true) {
+                panic!("sampled a non-model: {:?}", sample);
+            }
+
+            *frequencies.entry(sample.lits()).or_default() += 1;
+        }
+
+        // Pearson's chi-squared statistic over every model, including those never drawn
+        let expected = draws as f64 / models as f64;
+        let observed: f64 = frequencies
+            .values()
+            .map(|n| (*n as f64 - expected).powi(2) / expected)
+            .sum();
+        let statistic = observed + (models - frequencies.len()) as f64 * expected;
+
+        if statistic <= critical {
+            break;
+        }
+
+        statistics.push(statistic);
+    }
+
+    if statistics.len() == 3 {
+        panic!(
+            "non-uniform sampling: chi-squared {:.1?} all exceed {:.1}",
+            statistics, critical
+        );
+    }
+
+    for sample in hydra::samples(&formula, hydra::SamplingStrategy::RandomPolarity, 0).take(64) {
+        if formula.evaluate(&sample) != Some(true) {
+            panic!("sampled a non-model: {:?}", sample);
+        }
+    }
+}
+
 fn varisat_sat(formula: &hydra::Formula) -> bool {
     let mut v_formula = varisat::Solver::new();
 
@@ -0,0 +1,248 @@
+//! Weighted partial MaxSAT
+//!
+//! A MaxSAT instance has hard clauses, which must be satisfied, and weighted soft clauses.
+//! An optimal model satisfies every hard clause while minimising the total weight of the soft clauses it falsifies.
+
+use crate::{
+    encode, solve_with_assumptions, unsat_core, Assignment, Clause, Formula, Var, VarPool,
+};
+
+/// A formula of hard clauses together with weighted soft clauses.
+#[derive(Debug, Default, Clone)]
+pub struct WeightedFormula {
+    hard: Formula,
+    soft: Vec<(Clause, u64)>,
+}
+
+impl WeightedFormula {
+    /// Creates a new, empty, weighted formula.
+    pub fn new() -> WeightedFormula {
+        WeightedFormula::default()
+    }
+
+    /// Adds a clause that every solution must satisfy.
+    pub fn add_hard(&mut self, clause: Clause) {
+        self.hard.add_clause(clause);
+    }
+
+    /// Adds a clause that costs `weight` if a solution falsifies it.
+    pub fn add_soft(&mut self, clause: Clause, weight: u64) {
+        self.soft.push((clause, weight));
+    }
+
+    /// Gets the hard clauses as a formula.
+    pub fn hard(&self) -> &Formula {
+        &self.hard
+    }
+
+    /// Gets the soft clauses and their weights.
+    pub fn soft(&self) -> &Vec<(Clause, u64)> {
+        &self.soft
+    }
+
+    /// Returns the variables that occur in the hard or soft clauses, sorted by index.
+    pub fn vars(&self) -> Vec<Var> {
+        let mut vars = self.hard.vars();
+
+        for (clause, _) in &self.soft {
+            vars.extend(clause.literals().iter().map(|lit| lit.var()));
+        }
+
+        vars.sort();
+        vars.dedup();
+
+        vars
+    }
+
+    /// The total weight of soft clauses not satisfied by `assignment`.
+    pub fn cost(&self, assignment: &Assignment) -> u64 {
+        self.soft
+            .iter()
+            .filter(|(clause, _)| clause.evaluate(assignment) != Some(true))
+            .map(|(_, weight)| weight)
+            .sum()
+    }
+}
+
+/// The search strategy used to find an optimal model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaxSatAlgorithm {
+    /// Repeatedly finds a model and then requires the next to be strictly cheaper, until none exists.
+    /// Improves an upper bound on the optimal cost.
+    LinearSatUnsat,
+    /// Core-guided search: relaxes each unsatisfiable core of soft clauses in turn, splitting weights as in WPM1.
+    /// Improves a lower bound on the optimal cost.
+    FuMalik,
+}
+
+/// A bound on the optimal cost, reported as the search progresses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CostBound {
+    /// The optimal cost is at least this value.
+    Lower(u64),
+    /// A model with this cost has been found.
+    Upper(u64),
+}
+
+/// An optimal model of a weighted formula.
+#[derive(Debug, Clone)]
+pub struct MaxSatSolution {
+    model: Assignment,
+    cost: u64,
+}
+
+impl MaxSatSolution {
+    /// The optimal model, assigning every variable in the instance.
+    pub fn model(&self) -> &Assignment {
+        &self.model
+    }
+
+    /// The total weight of soft clauses falsified by the model.
+    pub fn cost(&self) -> u64 {
+        self.cost
+    }
+}
+
+/// Finds a model of the hard clauses that minimises the weight of falsified soft clauses.
+///
+/// Returns `None` if the hard clauses are unsatisfiable.
+pub fn solve_maxsat(
+    instance: &WeightedFormula,
+    algorithm: MaxSatAlgorithm,
+) -> Option<MaxSatSolution> {
+    solve_maxsat_with(instance, algorithm, |_| {})
+}
+
+/// As `solve_maxsat`, calling `on_bound` each time the lower or upper bound on the optimal cost improves.
+pub fn solve_maxsat_with(
+    instance: &WeightedFormula,
+    algorithm: MaxSatAlgorithm,
+    mut on_bound: impl FnMut(CostBound),
+) -> Option<MaxSatSolution> {
+    match algorithm {
+        MaxSatAlgorithm::LinearSatUnsat => linear_sat_unsat(instance, &mut on_bound),
+        MaxSatAlgorithm::FuMalik => fu_malik(instance, &mut on_bound),
+    }
+}
+
+fn linear_sat_unsat(
+    instance: &WeightedFormula,
+    on_bound: &mut impl FnMut(CostBound),
+) -> Option<MaxSatSolution> {
+    let vars = instance.vars();
+    let mut pool = VarPool::after(vars.last().copied());
+
+    // Each soft clause gets a relaxation literal that is true whenever the clause is falsified
+    let mut relaxed = instance.hard.clone();
+    let mut terms = Vec::new();
+
+    for (clause, weight) in &instance.soft {
+        if *weight == 0 {
+            continue;
+        }
+
+        let relax = pool.fresh().positive();
+        let mut clause = clause.clone();
+        clause.add_literal(relax);
+
+        relaxed.add_clause(clause);
+        terms.push((relax, *weight));
+    }
+
+    let mut formula = relaxed.clone();
+    let mut best = None;
+
+    while let Some(model) = solve_with_assumptions(&formula, &[]) {
+        let model = model.completed(&vars);
+        let cost = instance.cost(&model);
+
+        on_bound(CostBound::Upper(cost));
+        best = Some(MaxSatSolution { model, cost });
+
+        if cost == 0 {
+            break;
+        }
+
+        // Require a strictly cheaper model
+        formula = relaxed.clone();
+        encode::pb_at_most(&terms, cost - 1, &mut pool.clone(), &mut formula);
+    }
+
+    best
+}
+
+fn fu_malik(
+    instance: &WeightedFormula,
+    on_bound: &mut impl FnMut(CostBound),
+) -> Option<MaxSatSolution> {
+    let vars = instance.vars();
+    let mut pool = VarPool::after(vars.last().copied());
+
+    let mut hard = instance.hard.clone();
+    let mut soft: Vec<(Clause, u64)> = instance
+        .soft
+        .iter()
+        .filter(|(_, weight)| *weight > 0)
+        .cloned()
+        .collect();
+    let mut lower = 0;
+
+    loop {
+        // Each soft clause is enabled by assuming its selector literal is false
+        let mut formula = hard.clone();
+        let mut selectors = Vec::new();
+
+        for (clause, _) in &soft {
+            let selector = pool.fresh().positive();
+            let mut clause = clause.clone();
+            clause.add_literal(selector);
+
+            formula.add_clause(clause);
+            selectors.push(!selector);
+        }
+
+        if let Some(model) = solve_with_assumptions(&formula, &selectors) {
+            let model = model.completed(&vars);
+            let cost = instance.cost(&model);
+
+            on_bound(CostBound::Upper(cost));
+            return Some(MaxSatSolution { model, cost });
+        }
+
+        let core = unsat_core(&formula, &selectors)?;
+
+        // An empty core means the hard clauses alone are unsatisfiable
+        if core.is_empty() {
+            return None;
+        }
+
+        let core: Vec<usize> = core
+            .iter()
+            .map(|lit| selectors.iter().position(|s| s == lit).unwrap())
+            .collect();
+
+        let min_weight = core.iter().map(|i| soft[*i].1).min().unwrap();
+        lower += min_weight;
+        on_bound(CostBound::Lower(lower));
+
+        // Relax every clause in the core, keeping a copy of any weight above the minimum
+        let mut relaxations = Vec::new();
+
+        for i in core {
+            let (clause, weight) = soft[i].clone();
+            let relax = pool.fresh().positive();
+
+            let mut relaxed = clause.clone();
+            relaxed.add_literal(relax);
+            soft[i] = (relaxed, min_weight);
+
+            if weight > min_weight {
+                soft.push((clause, weight - min_weight));
+            }
+
+            relaxations.push(relax);
+        }
+
+        encode::at_most_one(&relaxations, &mut hard);
+    }
+}
@@ -0,0 +1,68 @@
+//! Minimal correction sets
+//!
+//! A minimal correction set (MCS) of a formula is a set of its clauses whose removal makes the formula satisfiable, while removing any proper subset does not.
+//! The complement of a minimal correction set is a maximal satisfiable subset (MSS), and the minimal correction sets are exactly the minimal hitting sets of the minimal unsatisfiable subsets.
+//! Each correction set is found with the CLD algorithm: starting from a model, the satisfied clauses are extended by asking for a model that also satisfies at least one of the rest, until none does.
+
+use crate::{mus::SubsetChecker, Formula, Lit, SolverConfig};
+
+/// An iterator over the minimal correction sets of the clauses of a formula, see `mcses`.
+pub struct Mcses {
+    checker: SubsetChecker,
+    done: bool,
+}
+
+impl Iterator for Mcses {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Vec<usize>> {
+        if self.done {
+            return None;
+        }
+
+        let Ok(model) = self.checker.check(&[]) else {
+            self.done = true;
+            return None;
+        };
+
+        let mut satisfied = self.checker.satisfied(&model);
+
+        let mcs = loop {
+            let rest: Vec<usize> = (0..self.checker.len())
+                .filter(|group| !satisfied.contains(group))
+                .collect();
+
+            // Asks for a model that keeps the satisfied clauses and also satisfies one of the rest
+            let clause: Vec<Lit> = rest
+                .iter()
+                .map(|group| self.checker.selector(*group))
+                .collect();
+
+            match self.checker.check_with_clause(&satisfied, &clause) {
+                Ok(model) => satisfied = self.checker.satisfied(&model),
+                Err(_) => break rest,
+            }
+        };
+
+        // Later correction sets differ, so their complements satisfy some clause of this one
+        let block: Vec<Lit> = mcs
+            .iter()
+            .map(|group| self.checker.selector(*group))
+            .collect();
+        self.checker.add_clause(block);
+
+        Some(mcs)
+    }
+}
+
+/// Enumerates the minimal correction sets of the clauses of `formula`, each as sorted indices into `formula.clauses()`.
+///
+/// A satisfiable formula has exactly one, the empty set. Parity and cardinality constraints are never removed, so if they are unsatisfiable on their own there are none.
+pub fn mcses(formula: &Formula) -> Mcses {
+    let groups: Vec<Vec<usize>> = (0..formula.clauses().len()).map(|i| vec![i]).collect();
+
+    Mcses {
+        checker: SubsetChecker::new(formula, &groups, SolverConfig::default()),
+        done: false,
+    }
+}
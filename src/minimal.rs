@@ -0,0 +1,77 @@
+//! Minimal models
+//!
+//! A model is subset-minimal if no other model makes a proper subset of its variables true, and cardinality-minimal if no other model makes fewer variables true.
+//! Every cardinality-minimal model is subset-minimal. A Horn formula has a single subset-minimal model, see `minimal_model`.
+
+use crate::{
+    encode, is_horn, minimal_model, Assignment, Formula, Lit, SolveResult, Solver, SolverConfig,
+    VarPool,
+};
+
+/// Finds a model of `formula` in which no set of true variables can be made false.
+///
+/// Each model found is improved by asking for another that keeps every false variable false and makes some true variable false, until there is none.
+///
+/// Returns `None` if the formula is unsatisfiable. Otherwise, the assignment assigns every variable in the formula.
+pub fn subset_minimal_model(formula: &Formula) -> Option<Assignment> {
+    if is_horn(formula) {
+        return minimal_model(formula);
+    }
+
+    let vars = formula.vars();
+    let mut pool = VarPool::after(formula.max_var());
+    let mut solver = Solver::from_formula(formula, SolverConfig::default());
+    let mut model = solver.solve().model()?.completed(&vars);
+
+    loop {
+        let (positive, negative): (Vec<Lit>, Vec<Lit>) =
+            model.lits().into_iter().partition(|lit| lit.is_positive());
+
+        // Asks for some true variable to become false while the false ones stay false
+        let clause: Vec<Lit> = positive.iter().map(|lit| !*lit).collect();
+
+        match solver.solve_with_temporary_clause(&clause, pool.fresh().positive(), &negative) {
+            SolveResult::Sat(smaller) => model = smaller.completed(&vars),
+            _ => return Some(model),
+        }
+    }
+}
+
+/// Finds a model of `formula` with the fewest true variables.
+///
+/// Searches linearly with a totaliser counting the true variables, added once.
+/// Each next model is found by assuming that fewer variables are true than in the last, until there is none.
+///
+/// Returns `None` if the formula is unsatisfiable. Otherwise, the assignment assigns every variable in the formula.
+pub fn cardinality_minimal_model(formula: &Formula) -> Option<Assignment> {
+    if is_horn(formula) {
+        return minimal_model(formula);
+    }
+
+    let vars = formula.vars();
+    let positive: Vec<Lit> = vars.iter().map(|var| var.positive()).collect();
+    let mut solver = Solver::from_formula(formula, SolverConfig::default());
+    let mut model = solver.solve().model()?.completed(&vars);
+    let true_count =
+        |model: &Assignment| model.lits().iter().filter(|lit| lit.is_positive()).count();
+
+    // Counts beyond that of the first model are never needed
+    let mut pool = VarPool::after(formula.max_var());
+    let mut totaliser = Formula::new();
+    let outputs = encode::totaliser(&positive, true_count(&model), &mut pool, &mut totaliser);
+    solver.add_formula(&totaliser);
+
+    loop {
+        let count = true_count(&model);
+
+        if count == 0 {
+            return Some(model);
+        }
+
+        // Output `count - 1` is true exactly when at least `count` variables are
+        match solver.solve_with_assumptions(&[!outputs[count - 1]]) {
+            SolveResult::Sat(smaller) => model = smaller.completed(&vars),
+            _ => return Some(model),
+        }
+    }
+}
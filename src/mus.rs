@@ -0,0 +1,459 @@
+//! Minimal unsatisfiable subsets
+//!
+//! A minimal unsatisfiable subset (MUS) of an unsatisfiable formula is a set of its clauses that is unsatisfiable, but becomes satisfiable if any one clause is removed.
+//! Each clause, or group of clauses, is guarded by a selector literal, so that one incremental solver can check any subset by assuming the selectors of that subset.
+//! Every unsatisfiable check yields a core, a subset of the assumed selectors that is already unsatisfiable, which clause-set refinement uses to discard everything outside it.
+//! Every satisfiable check yields a model, which model rotation uses to find further clauses that must be in every MUS of the remaining subset.
+
+use crate::{Assignment, Clause, Formula, Lit, SolveResult, Solver, SolverConfig, Var, VarPool};
+
+/// The search strategy used to find a minimal unsatisfiable subset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MusAlgorithm {
+    /// Tries removing each clause in turn, keeping the removal if the rest stays unsatisfiable.
+    /// Uses one solver call per clause.
+    Deletion,
+    /// Divide and conquer, recursively splitting the candidates in half.
+    /// Uses fewer solver calls than deletion when the MUS is small compared to the formula.
+    QuickXplain,
+}
+
+/// Configuration for `mus` and `group_mus`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MusConfig {
+    pub algorithm: MusAlgorithm,
+    /// Whether to shrink the candidates to the core of every unsatisfiable check.
+    pub refine: bool,
+    /// Whether to use the models of satisfiable checks to find more necessary clauses.
+    /// Only used by deletion.
+    pub rotate: bool,
+    /// Configuration of the incremental solver used for every check.
+    pub solver: SolverConfig,
+}
+
+impl Default for MusConfig {
+    fn default() -> MusConfig {
+        MusConfig {
+            algorithm: MusAlgorithm::Deletion,
+            refine: true,
+            rotate: true,
+            solver: SolverConfig::default(),
+        }
+    }
+}
+
+/// Finds a minimal unsatisfiable subset of the clauses of `formula`.
+///
+/// Returns `None` if the formula is satisfiable. Otherwise, returns sorted indices into `formula.clauses()`.
+/// Parity and cardinality constraints are kept in every subset, so the result is empty when they are unsatisfiable on their own.
+pub fn mus(formula: &Formula, config: &MusConfig) -> Option<Vec<usize>> {
+    let groups: Vec<Vec<usize>> = (0..formula.clauses().len()).map(|i| vec![i]).collect();
+    let mut checker = SubsetChecker::new(formula, &groups, config.solver.clone());
+
+    find_mus(&mut checker, config)
+}
+
+/// Finds a minimal unsatisfiable set of named groups of clauses of `formula`, where each group is given by indices into `formula.clauses()`.
+///
+/// Clauses in no group, and parity and cardinality constraints, are kept in every subset.
+/// Returns `None` if the formula is satisfiable. Otherwise, returns the names of the groups in the set, in the order they were given.
+pub fn group_mus<'a>(
+    formula: &Formula,
+    groups: &'a [(String, Vec<usize>)],
+    config: &MusConfig,
+) -> Option<Vec<&'a str>> {
+    let indices: Vec<Vec<usize>> = groups.iter().map(|(_, group)| group.clone()).collect();
+    let mut checker = SubsetChecker::new(formula, &indices, config.solver.clone());
+
+    let mus = find_mus(&mut checker, config)?;
+
+    Some(mus.into_iter().map(|i| groups[i].0.as_str()).collect())
+}
+
+/// Finds a minimal unsatisfiable set of the groups of `checker`, as sorted group indices.
+fn find_mus(checker: &mut SubsetChecker, config: &MusConfig) -> Option<Vec<usize>> {
+    let all: Vec<usize> = (0..checker.len()).collect();
+
+    match checker.check(&all) {
+        Ok(_) => None,
+        Err(core) if config.refine => Some(shrink(checker, core, config)),
+        Err(_) => Some(shrink(checker, all, config)),
+    }
+}
+
+/// Shrinks an unsatisfiable set of groups to a minimal one, returning sorted group indices.
+fn shrink(checker: &mut SubsetChecker, working: Vec<usize>, config: &MusConfig) -> Vec<usize> {
+    let mut mus = match config.algorithm {
+        MusAlgorithm::Deletion => deletion(checker, working, config),
+        MusAlgorithm::QuickXplain if working.is_empty() => Vec::new(),
+        MusAlgorithm::QuickXplain => quick_xplain(checker, &mut Vec::new(), false, &working),
+    };
+
+    mus.sort();
+
+    mus
+}
+
+/// Grows a satisfiable set of groups, with a model satisfying it, to a maximal one, returning sorted group indices.
+fn grow(checker: &mut SubsetChecker, model: &Assignment) -> Vec<usize> {
+    let mut mss = checker.satisfied(model);
+
+    for group in 0..checker.len() {
+        if mss.contains(&group) {
+            continue;
+        }
+
+        let mut candidate = mss.clone();
+        candidate.push(group);
+
+        if let Ok(model) = checker.check(&candidate) {
+            mss = checker.satisfied(&model);
+        }
+    }
+
+    mss
+}
+
+/// A subset of the clauses of a formula found by `marco`, as sorted indices into `formula.clauses()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarcoSubset {
+    /// A minimal unsatisfiable subset.
+    Mus(Vec<usize>),
+    /// A maximal satisfiable subset, whose complement is a minimal correction set.
+    Mss(Vec<usize>),
+}
+
+/// An iterator over the minimal unsatisfiable and maximal satisfiable subsets of the clauses of a formula, see `marco`.
+pub struct Marco {
+    checker: SubsetChecker,
+    /// A solver whose models are the subsets not yet explored, with variable `i` true when clause `i` is excluded.
+    map: Solver,
+    config: MusConfig,
+}
+
+impl Iterator for Marco {
+    type Item = MarcoSubset;
+
+    fn next(&mut self) -> Option<MarcoSubset> {
+        let SolveResult::Sat(model) = self.map.solve() else {
+            return None;
+        };
+
+        // Clauses the map leaves unassigned are included, since the solver prefers false values
+        let seed: Vec<usize> = (0..self.checker.len())
+            .filter(|i| model.get(&map_var(*i)) != Some(true))
+            .collect();
+
+        match self.checker.check(&seed) {
+            Ok(model) => {
+                let mss = grow(&mut self.checker, &model);
+
+                // Every subset of a satisfiable set is satisfiable, so some clause outside it must be included
+                let block: Vec<Lit> = (0..self.checker.len())
+                    .filter(|i| !mss.contains(i))
+                    .map(|i| map_var(i).negative())
+                    .collect();
+                self.map.add_clause(&block.into());
+
+                Some(MarcoSubset::Mss(mss))
+            }
+            Err(core) => {
+                let working = if self.config.refine { core } else { seed };
+                let mus = shrink(&mut self.checker, working, &self.config);
+
+                // Every superset of an unsatisfiable set is unsatisfiable, so some clause of it must be excluded
+                let block: Vec<Lit> = mus.iter().map(|i| map_var(*i).positive()).collect();
+                self.map.add_clause(&block.into());
+
+                Some(MarcoSubset::Mus(mus))
+            }
+        }
+    }
+}
+
+/// Enumerates every minimal unsatisfiable subset and every maximal satisfiable subset of the clauses of `formula`, in the style of MARCO.
+///
+/// Each subset is explored from a seed, a subset not yet known to be a subset of a satisfiable set or a superset of an unsatisfiable one.
+/// A satisfiable seed is grown to a maximal satisfiable subset, and an unsatisfiable seed is shrunk to a minimal unsatisfiable subset with the configured algorithm.
+/// Parity and cardinality constraints are kept in every subset.
+pub fn marco(formula: &Formula, config: &MusConfig) -> Marco {
+    let groups: Vec<Vec<usize>> = (0..formula.clauses().len()).map(|i| vec![i]).collect();
+
+    Marco {
+        checker: SubsetChecker::new(formula, &groups, config.solver.clone()),
+        map: Solver::new(SolverConfig::default()),
+        config: config.clone(),
+    }
+}
+
+/// The variable of the map solver for clause `index`.
+fn map_var(index: usize) -> Var {
+    Var::from_index(index).unwrap()
+}
+
+/// Shrinks an unsatisfiable set of groups by trying to remove each group in turn.
+fn deletion(
+    checker: &mut SubsetChecker,
+    mut working: Vec<usize>,
+    config: &MusConfig,
+) -> Vec<usize> {
+    // Groups whose removal makes the working set satisfiable
+    let mut necessary = vec![false; checker.len()];
+
+    while let Some(group) = working.iter().copied().find(|group| !necessary[*group]) {
+        let candidate: Vec<usize> = working.iter().copied().filter(|g| *g != group).collect();
+
+        match checker.check(&candidate) {
+            Err(core) if config.refine => working = core,
+            Err(_) => working = candidate,
+            Ok(model) => {
+                necessary[group] = true;
+
+                if config.rotate {
+                    checker.rotate(model, group, &working, &mut necessary);
+                }
+            }
+        }
+    }
+
+    working
+}
+
+/// Finds a minimal subset of `candidates` that is unsatisfiable together with `background`.
+///
+/// `changed` is `false` if `background` is known to be satisfiable on its own.
+fn quick_xplain(
+    checker: &mut SubsetChecker,
+    background: &mut Vec<usize>,
+    changed: bool,
+    candidates: &[usize],
+) -> Vec<usize> {
+    if changed && checker.check(background).is_err() {
+        return Vec::new();
+    }
+
+    if candidates.len() == 1 {
+        return candidates.to_vec();
+    }
+
+    let (left, right) = candidates.split_at(candidates.len() / 2);
+    let len = background.len();
+
+    background.extend(left);
+    let mut right = quick_xplain(checker, background, true, right);
+    background.truncate(len);
+
+    background.extend(&right);
+    let mut left = quick_xplain(checker, background, !right.is_empty(), left);
+    background.truncate(len);
+
+    left.append(&mut right);
+
+    left
+}
+
+/// An incremental solver that checks whether subsets of groups of clauses are satisfiable together with the remaining hard constraints.
+pub(crate) struct SubsetChecker {
+    solver: Solver,
+    /// The variable of the selector of the first group, with the rest following in order.
+    first_selector: Var,
+    /// The clauses of each group.
+    groups: Vec<Vec<Clause>>,
+    /// Clauses in no group, and any parity and cardinality constraints.
+    hard: Formula,
+    /// The variables of the original formula.
+    vars: Vec<Var>,
+    /// Hands out variables after the selectors.
+    pool: VarPool,
+}
+
+impl SubsetChecker {
+    /// Creates a checker for groups of `formula.clauses()` given by their indices.
+    pub fn new(formula: &Formula, groups: &[Vec<usize>], config: SolverConfig) -> SubsetChecker {
+        let mut pool = VarPool::after(formula.max_var());
+        let mut solver = Solver::new(config);
+        let mut grouped = vec![false; formula.clauses().len()];
+        let mut first_selector = None;
+
+        let groups: Vec<Vec<Clause>> = groups
+            .iter()
+            .map(|indices| {
+                // A group is enforced while its selector is true
+                let selector = pool.fresh();
+                first_selector.get_or_insert(selector);
+
+                indices
+                    .iter()
+                    .map(|index| {
+                        let clause = formula.clauses()[*index].clone();
+                        let mut guarded = clause.clone();
+                        guarded.add_literal(selector.negative());
+                        solver.add_clause(&guarded);
+                        grouped[*index] = true;
+
+                        clause
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut hard = Formula::new();
+
+        for (clause, grouped) in formula.clauses().iter().zip(grouped) {
+            if !grouped {
+                hard.add_clause(clause.clone());
+            }
+        }
+
+        for xor in formula.xors() {
+            hard.add_xor(xor.clone());
+        }
+
+        for cardinality in formula.cardinalities() {
+            hard.add_cardinality(cardinality.clone());
+        }
+
+        solver.add_formula(&hard);
+
+        SubsetChecker {
+            solver,
+            first_selector: first_selector.unwrap_or_else(|| pool.fresh()),
+            groups,
+            hard,
+            vars: formula.vars(),
+            pool,
+        }
+    }
+
+    /// The number of groups.
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// The literal that enforces a group while it is true.
+    pub fn selector(&self, group: usize) -> Lit {
+        Var::from_index(self.first_selector.index() + group)
+            .unwrap()
+            .positive()
+    }
+
+    /// Checks whether the given groups are satisfiable together with the hard constraints.
+    ///
+    /// Returns a model assigning every variable of the original formula, or a sorted subset of `groups` that is unsatisfiable on its own.
+    pub fn check(&mut self, groups: &[usize]) -> Result<Assignment, Vec<usize>> {
+        let result = self.solver.solve_with_assumptions(&self.selectors(groups));
+
+        self.outcome(result)
+    }
+
+    /// Checks the given groups as `check` does, with `clause` also required for this check only.
+    ///
+    /// Any core is of the groups alone, so is only unsatisfiable on its own together with the clause.
+    pub fn check_with_clause(
+        &mut self,
+        groups: &[usize],
+        clause: &[Lit],
+    ) -> Result<Assignment, Vec<usize>> {
+        let activation = self.pool.fresh().positive();
+        let assumptions = self.selectors(groups);
+        let result = self
+            .solver
+            .solve_with_temporary_clause(clause, activation, &assumptions);
+
+        self.outcome(result)
+    }
+
+    /// The selectors of the given groups.
+    fn selectors(&self, groups: &[usize]) -> Vec<Lit> {
+        groups.iter().map(|group| self.selector(*group)).collect()
+    }
+
+    /// The model or core of a check, from the result of solving under selectors and any other assumptions.
+    fn outcome(&self, result: SolveResult) -> Result<Assignment, Vec<usize>> {
+        match result {
+            SolveResult::Sat(model) => Ok(model.completed(&self.vars)),
+            SolveResult::Unsat => {
+                let selectors =
+                    self.first_selector.index()..self.first_selector.index() + self.len();
+                let mut core: Vec<usize> = self
+                    .solver
+                    .failed_assumptions()
+                    .iter()
+                    .filter(|lit| selectors.contains(&lit.index()))
+                    .map(|lit| lit.index() - self.first_selector.index())
+                    .collect();
+                core.sort();
+
+                Err(core)
+            }
+            SolveResult::Unknown => unreachable!("subset checks are never interrupted"),
+        }
+    }
+
+    /// Adds a clause, over the original variables, selectors and fresh literals, that applies to every later check.
+    pub fn add_clause(&mut self, lits: Vec<Lit>) {
+        self.solver.add_clause(&lits.into());
+    }
+
+    /// The groups with every clause satisfied by `model`.
+    pub fn satisfied(&self, model: &Assignment) -> Vec<usize> {
+        (0..self.len())
+            .filter(|group| {
+                self.groups[*group]
+                    .iter()
+                    .all(|clause| clause.evaluate(model) == Some(true))
+            })
+            .collect()
+    }
+
+    /// The groups of `among` with a clause falsified by `model`.
+    pub fn falsified(&self, model: &Assignment, among: &[usize]) -> Vec<usize> {
+        among
+            .iter()
+            .copied()
+            .filter(|group| {
+                self.groups[*group]
+                    .iter()
+                    .any(|clause| clause.evaluate(model) == Some(false))
+            })
+            .collect()
+    }
+
+    /// Recursive model rotation.
+    ///
+    /// `model` satisfies the hard constraints and every group of `working` except `group`.
+    /// Flipping a variable of a falsified clause of `group` may give a model that falsifies exactly one other group of `working`, which is then also necessary.
+    pub fn rotate(
+        &self,
+        model: Assignment,
+        group: usize,
+        working: &[usize],
+        necessary: &mut [bool],
+    ) {
+        let mut stack = vec![(model, group)];
+
+        while let Some((model, group)) = stack.pop() {
+            for clause in &self.groups[group] {
+                if clause.evaluate(&model) != Some(false) {
+                    continue;
+                }
+
+                for lit in clause.literals() {
+                    let mut rotated = model.clone();
+                    rotated.set_lit(&lit);
+
+                    if self.hard.evaluate(&rotated) != Some(true) {
+                        continue;
+                    }
+
+                    if let [other] = self.falsified(&rotated, working)[..] {
+                        if !necessary[other] {
+                            necessary[other] = true;
+                            stack.push((rotated, other));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,93 @@
+//! Parallel portfolio solving
+//!
+//! Runs several differently configured solvers on the same formula in parallel with rayon.
+//! The first to finish wins and interrupts the others. Solvers can also share their short learned clauses.
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
+
+use crate::{cdcl::ClauseExchange, Formula, Polarity, Restarts, SolveResult, Solver, SolverConfig};
+
+/// Configuration for a portfolio of solvers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortfolioConfig {
+    /// One solver is run with each configuration.
+    pub workers: Vec<SolverConfig>,
+    /// Learned clauses with at most this many literals are shared between workers, or none if `None`.
+    pub share_max_len: Option<usize>,
+}
+
+impl Default for PortfolioConfig {
+    fn default() -> PortfolioConfig {
+        let workers = vec![
+            SolverConfig::default(),
+            SolverConfig {
+                seed: 1,
+                polarity: Polarity::Negative,
+                restarts: Restarts::Geometric {
+                    first: 100,
+                    factor: 1.5,
+                },
+                ..SolverConfig::default()
+            },
+            SolverConfig {
+                seed: 2,
+                var_decay: 0.8,
+                random_var_freq: 0.02,
+                restarts: Restarts::Luby { unit: 512 },
+                ..SolverConfig::default()
+            },
+            SolverConfig {
+                seed: 3,
+                polarity: Polarity::Random,
+                random_var_freq: 0.05,
+                restarts: Restarts::Never,
+                ..SolverConfig::default()
+            },
+        ];
+
+        PortfolioConfig {
+            workers,
+            share_max_len: Some(8),
+        }
+    }
+}
+
+/// Solves `formula` with every configured worker in parallel, returning the first definite result.
+///
+/// Returns `SolveResult::Unknown` only if there are no workers.
+pub fn solve_portfolio(formula: &Formula, config: &PortfolioConfig) -> SolveResult {
+    let stop = Arc::new(AtomicBool::new(false));
+    let exchange = Arc::new(ClauseExchange::default());
+
+    let result = config
+        .workers
+        .par_iter()
+        .enumerate()
+        .find_map_any(|(id, worker)| {
+            if stop.load(Ordering::Relaxed) {
+                return None;
+            }
+
+            let mut solver = Solver::from_formula(formula, worker.clone());
+            solver.set_interrupt(stop.clone());
+
+            if let Some(max_len) = config.share_max_len {
+                solver.share_clauses(exchange.clone(), id, max_len);
+            }
+
+            match solver.solve() {
+                SolveResult::Unknown => None,
+                result => {
+                    stop.store(true, Ordering::Relaxed);
+                    Some(result)
+                }
+            }
+        });
+
+    result.unwrap_or(SolveResult::Unknown)
+}
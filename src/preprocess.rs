@@ -0,0 +1,457 @@
+//! CNF preprocessing
+//!
+//! Simplifies a formula before search with a pipeline of passes, recording how to extend a model of the simplified formula back to the original variables.
+//! Equivalent literal substitution and bounded variable elimination both remove variables, and are undone in reverse when extending a model.
+//! Symmetry breaking instead adds clauses, and auxiliary variables, which are dropped when extending a model.
+//! Bounded variable elimination follows SatELite: a variable is replaced by all resolvents of its clauses when that does not increase the number of clauses.
+
+use crate::{
+    equivalence::substitute_equivalences,
+    formula::clausal,
+    solve_with,
+    subsume::{is_subsumed, subsume_all},
+    symmetry::symmetry_breaking_clauses,
+    Assignment, Engine, Formula, Lit, SolveResult, SubsumptionStats, Var, VarPool,
+};
+
+/// A simplification applied by `preprocess`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pass {
+    /// Bounded variable elimination by clause distribution.
+    VariableElimination,
+    /// Removal of subsumed clauses and self-subsuming strengthening, see `simplify`.
+    Subsumption,
+    /// Replacement of equivalent literals by a representative, see `equivalent_literals`.
+    EquivalentLiterals,
+    /// Addition of lex-leader predicates for the symmetries of the formula, see `break_symmetries`.
+    /// Not run by default, since the automorphism search can be expensive.
+    SymmetryBreaking,
+}
+
+/// Configuration for `preprocess`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreprocessConfig {
+    /// The passes to run, in order.
+    pub passes: Vec<Pass>,
+    /// Variables occurring in more clauses than this are not eliminated.
+    pub max_occurrences: usize,
+    /// Variables are not eliminated if any resolvent would have more literals than this.
+    pub max_resolvent_len: usize,
+}
+
+impl Default for PreprocessConfig {
+    fn default() -> PreprocessConfig {
+        PreprocessConfig {
+            passes: vec![
+                Pass::EquivalentLiterals,
+                Pass::Subsumption,
+                Pass::VariableElimination,
+            ],
+            max_occurrences: 32,
+            max_resolvent_len: 16,
+        }
+    }
+}
+
+/// Counters describing the simplifications made by `preprocess`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PreprocessStats {
+    pub eliminated_vars: usize,
+    /// Clauses removed by variable elimination.
+    pub removed_clauses: usize,
+    /// Resolvents added by variable elimination.
+    pub added_clauses: usize,
+    pub subsumption: SubsumptionStats,
+    /// Variables replaced by an equivalent literal.
+    pub substituted_vars: usize,
+    /// Symmetries broken by lex-leader predicates.
+    pub broken_symmetries: usize,
+    /// Clauses added by lex-leader predicates.
+    pub symmetry_breaking_clauses: usize,
+}
+
+/// The steps needed to extend a model of a simplified formula to the variables it no longer contains.
+#[derive(Debug, Clone, Default)]
+pub struct Reconstruction {
+    /// Removed clauses, each with the literal to make true if the clause is falsified, in the order they were removed.
+    stack: Vec<(Lit, Vec<Lit>)>,
+}
+
+impl Reconstruction {
+    /// Records a clause removed while eliminating the variable of `pivot`, which is in the clause.
+    pub(crate) fn push(&mut self, pivot: Lit, lits: Vec<Lit>) {
+        self.stack.push((pivot, lits));
+    }
+
+    /// The variables of the removed clauses, sorted by index.
+    pub fn vars(&self) -> Vec<Var> {
+        let mut vars: Vec<Var> = self
+            .stack
+            .iter()
+            .flat_map(|(_, lits)| lits.iter().map(|lit| lit.var()))
+            .collect();
+
+        vars.sort();
+        vars.dedup();
+
+        vars
+    }
+
+    /// Extends `model` in place, undoing the removals in reverse order.
+    ///
+    /// Every variable in the removed clauses must be assigned in `model`.
+    pub fn extend(&self, model: &mut Assignment) {
+        for (pivot, lits) in self.stack.iter().rev() {
+            if !lits.iter().any(|lit| model.evaluate(lit) == Some(true)) {
+                model.set_lit(pivot);
+            }
+        }
+    }
+}
+
+/// A simplified formula, equisatisfiable with the original.
+#[derive(Debug, Clone)]
+pub struct Preprocessed {
+    formula: Formula,
+    /// The variables of the original formula.
+    vars: Vec<Var>,
+    reconstruction: Reconstruction,
+    stats: PreprocessStats,
+}
+
+impl Preprocessed {
+    /// The simplified formula.
+    pub fn formula(&self) -> &Formula {
+        &self.formula
+    }
+
+    /// The steps needed to extend a model of the simplified formula.
+    pub fn reconstruction(&self) -> &Reconstruction {
+        &self.reconstruction
+    }
+
+    pub fn stats(&self) -> &PreprocessStats {
+        &self.stats
+    }
+
+    /// Extends a model of the simplified formula to a model of the original formula, assigning exactly the original variables.
+    pub fn extend(&self, model: &Assignment) -> Assignment {
+        // Auxiliary variables added by preprocessing may have been removed by later passes
+        let mut vars = self.vars.clone();
+        vars.extend(self.formula.vars());
+        vars.extend(self.reconstruction.vars());
+        vars.sort();
+        vars.dedup();
+
+        let mut model = model.completed(&vars);
+        self.reconstruction.extend(&mut model);
+
+        model.completed(&self.vars)
+    }
+}
+
+/// Simplifies `formula` with the configured passes.
+///
+/// The simplified formula is satisfiable exactly when the original is. If it is found to be unsatisfiable, it contains an empty clause.
+pub fn preprocess(formula: &Formula, config: &PreprocessConfig) -> Preprocessed {
+    let mut db = ClauseDb::new(formula);
+    let mut reconstruction = Reconstruction::default();
+    let mut stats = PreprocessStats::default();
+    // Auxiliary variables of the clausal encoding may already follow those of the formula
+    let mut pool = VarPool::after(db.vars().last().copied().max(formula.max_var()));
+
+    for pass in &config.passes {
+        if db.is_unsat() {
+            break;
+        }
+
+        match pass {
+            Pass::VariableElimination => {
+                eliminate_variables(&mut db, config, &mut reconstruction, &mut stats)
+            }
+            Pass::Subsumption => subsume_all(&mut db, &mut stats.subsumption),
+            Pass::EquivalentLiterals => {
+                substitute_equivalences(&mut db, &mut reconstruction, &mut stats)
+            }
+            Pass::SymmetryBreaking => {
+                let (symmetries, clauses) = symmetry_breaking_clauses(&db.to_formula(), &mut pool);
+                stats.broken_symmetries += symmetries;
+                stats.symmetry_breaking_clauses += clauses.len();
+
+                for clause in clauses {
+                    db.add(clause.literals());
+                }
+            }
+        }
+    }
+
+    Preprocessed {
+        formula: db.to_formula(),
+        vars: formula.vars(),
+        reconstruction,
+        stats,
+    }
+}
+
+/// Preprocesses `formula`, solves the simplified formula with `engine`, and extends any model to the original variables.
+pub fn solve_with_preprocessing(
+    formula: &Formula,
+    config: &PreprocessConfig,
+    engine: &Engine,
+) -> SolveResult {
+    let preprocessed = preprocess(formula, config);
+
+    match solve_with(preprocessed.formula(), engine) {
+        SolveResult::Sat(model) => SolveResult::Sat(preprocessed.extend(&model)),
+        result => result,
+    }
+}
+
+/// Clauses with occurrence lists, supporting removal.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ClauseDb {
+    /// Sorted, duplicate free, non-tautological clauses, or `None` once removed.
+    clauses: Vec<Option<Vec<Lit>>>,
+    /// Indices of clauses that contain, or once contained, each literal, indexed by `Lit::code`.
+    occurrences: Vec<Vec<usize>>,
+    /// A bit set of the variables in each clause, modulo 64, for fast subset tests.
+    signatures: Vec<u64>,
+    /// Whether an empty clause has been added.
+    unsat: bool,
+}
+
+impl ClauseDb {
+    /// Creates a database of the clauses of `formula`, with any parity constraints replaced by clauses.
+    pub fn new(formula: &Formula) -> ClauseDb {
+        let mut db = ClauseDb::default();
+
+        for clause in clausal(formula).clauses() {
+            db.add(clause.literals());
+        }
+
+        db
+    }
+
+    pub fn is_unsat(&self) -> bool {
+        self.unsat
+    }
+
+    /// Adds a clause, returning its index unless it is a tautology.
+    pub fn add(&mut self, mut lits: Vec<Lit>) -> Option<usize> {
+        lits.sort();
+        lits.dedup();
+
+        if lits.windows(2).any(|pair| pair[0] == !pair[1]) {
+            return None;
+        }
+
+        if lits.is_empty() {
+            self.unsat = true;
+        }
+
+        let index = self.clauses.len();
+
+        for lit in &lits {
+            if self.occurrences.len() <= lit.code() {
+                self.occurrences.resize((lit.index() + 1) * 2, Vec::new());
+            }
+
+            self.occurrences[lit.code()].push(index);
+        }
+
+        self.signatures.push(signature(&lits));
+        self.clauses.push(Some(lits));
+
+        Some(index)
+    }
+
+    /// Removes a clause, returning its literals.
+    pub fn remove(&mut self, index: usize) -> Vec<Lit> {
+        self.clauses[index].take().expect("clause already removed")
+    }
+
+    /// Removes `lit` from a clause.
+    pub fn strengthen(&mut self, index: usize, lit: Lit) {
+        let lits = self.clauses[index].as_mut().expect("clause removed");
+        lits.retain(|other| *other != lit);
+
+        if lits.is_empty() {
+            self.unsat = true;
+        }
+
+        self.signatures[index] = signature(lits);
+    }
+
+    pub fn signature(&self, index: usize) -> u64 {
+        self.signatures[index]
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Vec<Lit>> {
+        self.clauses[index].as_ref()
+    }
+
+    /// Indices of the clauses currently containing `lit`.
+    pub fn occurrences(&self, lit: Lit) -> Vec<usize> {
+        let Some(indices) = self.occurrences.get(lit.code()) else {
+            return Vec::new();
+        };
+
+        let mut indices: Vec<usize> = indices
+            .iter()
+            .copied()
+            .filter(|index| self.get(*index).is_some_and(|lits| lits.contains(&lit)))
+            .collect();
+
+        indices.dedup();
+
+        indices
+    }
+
+    /// Indices of every clause not yet removed.
+    pub fn indices(&self) -> Vec<usize> {
+        (0..self.clauses.len())
+            .filter(|index| self.clauses[*index].is_some())
+            .collect()
+    }
+
+    /// The variables that occur in some clause, sorted by index.
+    pub fn vars(&self) -> Vec<Var> {
+        (0..self.occurrences.len() / 2)
+            .map(|index| Var::from_index(index).unwrap())
+            .filter(|var| {
+                !self.occurrences(var.positive()).is_empty()
+                    || !self.occurrences(var.negative()).is_empty()
+            })
+            .collect()
+    }
+
+    pub fn to_formula(&self) -> Formula {
+        let mut formula = Formula::new();
+
+        for lits in self.clauses.iter().flatten() {
+            formula.add_clause(lits.clone().into());
+        }
+
+        formula
+    }
+}
+
+/// Eliminates variables by resolution until no more can be eliminated without adding clauses.
+fn eliminate_variables(
+    db: &mut ClauseDb,
+    config: &PreprocessConfig,
+    reconstruction: &mut Reconstruction,
+    stats: &mut PreprocessStats,
+) {
+    let mut changed = true;
+
+    while changed && !db.is_unsat() {
+        changed = false;
+
+        // Try the cheapest variables first
+        let mut vars: Vec<(Var, usize)> = db
+            .vars()
+            .into_iter()
+            .map(|var| {
+                let cost =
+                    db.occurrences(var.positive()).len() * db.occurrences(var.negative()).len();
+                (var, cost)
+            })
+            .collect();
+        vars.sort_by_key(|(var, cost)| (*cost, *var));
+
+        for (var, _) in vars {
+            if eliminate(db, var, config, reconstruction, stats) {
+                changed = true;
+
+                if db.is_unsat() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Replaces the clauses containing `var` by their resolvents on `var`, if that does not increase the number of clauses.
+///
+/// Returns `true` if the variable was eliminated.
+fn eliminate(
+    db: &mut ClauseDb,
+    var: Var,
+    config: &PreprocessConfig,
+    reconstruction: &mut Reconstruction,
+    stats: &mut PreprocessStats,
+) -> bool {
+    let positive = db.occurrences(var.positive());
+    let negative = db.occurrences(var.negative());
+
+    if positive.is_empty() && negative.is_empty() {
+        return false;
+    }
+
+    if positive.len() + negative.len() > config.max_occurrences {
+        return false;
+    }
+
+    let mut resolvents = Vec::new();
+
+    for p in &positive {
+        for n in &negative {
+            let Some(resolvent) = resolve(db.get(*p).unwrap(), db.get(*n).unwrap(), var) else {
+                continue;
+            };
+
+            if resolvent.len() > config.max_resolvent_len
+                || resolvents.len() >= positive.len() + negative.len()
+            {
+                return false;
+            }
+
+            resolvents.push(resolvent);
+        }
+    }
+
+    for (pivot, indices) in [(var.positive(), &positive), (var.negative(), &negative)] {
+        for index in indices {
+            reconstruction.push(pivot, db.remove(*index));
+            stats.removed_clauses += 1;
+        }
+    }
+
+    // Resolvents already subsumed by a remaining clause are redundant
+    for resolvent in resolvents {
+        if !is_subsumed(db, &resolvent) {
+            db.add(resolvent);
+            stats.added_clauses += 1;
+        }
+    }
+
+    stats.eliminated_vars += 1;
+
+    true
+}
+
+/// A bit set of the variables in a clause, modulo 64.
+pub(crate) fn signature(lits: &[Lit]) -> u64 {
+    lits.iter()
+        .fold(0, |sig, lit| sig | 1 << (lit.index() % 64))
+}
+
+/// The resolvent of two clauses on `var`, or `None` if it is a tautology.
+pub(crate) fn resolve(positive: &[Lit], negative: &[Lit], var: Var) -> Option<Vec<Lit>> {
+    let mut resolvent: Vec<Lit> = positive
+        .iter()
+        .chain(negative)
+        .copied()
+        .filter(|lit| lit.var() != var)
+        .collect();
+
+    resolvent.sort();
+    resolvent.dedup();
+
+    if resolvent.windows(2).any(|pair| pair[0] == !pair[1]) {
+        return None;
+    }
+
+    Some(resolvent)
+}
@@ -0,0 +1,418 @@
+//! Quantified Boolean formulas
+//!
+//! A prenex QBF is a sequence of quantifier blocks followed by a CNF matrix, and is true when the existential player can always respond to the universal player so that the matrix is satisfied.
+//! Solving follows RAReQS: the player of the outermost block proposes a move that wins against an abstraction, and the opponent answers with a counter-move found recursively.
+//! Each counter-move refines the abstraction with a copy of the rest of the formula, expanded on that move, until a proposal wins or the abstraction has no winning move.
+//! Matrices are kept as circuits of conjunctions and disjunctions so that the universal player can play on their negation, with hydra solving each innermost block as SAT.
+
+use std::collections::HashMap;
+
+use crate::{Assignment, Formula, Lit, SolveResult, Solver, SolverConfig, Var, VarPool};
+
+/// The quantifier of a block of variables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quantifier {
+    /// Some value of each variable makes the rest of the formula true.
+    Exists,
+    /// Every value of each variable makes the rest of the formula true.
+    Forall,
+}
+
+/// A quantified Boolean formula in prenex form.
+///
+/// Variables of the matrix bound by no block are free, and are treated as an outermost existential block.
+#[derive(Debug, Clone, Default)]
+pub struct Qbf {
+    prefix: Vec<(Quantifier, Vec<Var>)>,
+    matrix: Formula,
+}
+
+impl Qbf {
+    /// Creates a QBF with an empty prefix, so every variable of `matrix` is free.
+    pub fn new(matrix: Formula) -> Qbf {
+        Qbf {
+            prefix: Vec::new(),
+            matrix,
+        }
+    }
+
+    /// Adds a block of variables inside every existing block, merging it with the innermost block if that has the same quantifier.
+    ///
+    /// Each variable should be bound by at most one block.
+    pub fn add_block(&mut self, quantifier: Quantifier, vars: &[Var]) {
+        match self.prefix.last_mut() {
+            Some((last, block)) if *last == quantifier => block.extend(vars),
+            _ => self.prefix.push((quantifier, vars.to_vec())),
+        }
+    }
+
+    /// The quantifier blocks, outermost first.
+    pub fn prefix(&self) -> &[(Quantifier, Vec<Var>)] {
+        &self.prefix
+    }
+
+    pub fn matrix(&self) -> &Formula {
+        &self.matrix
+    }
+
+    /// Variables of the matrix bound by no block, sorted by index.
+    pub fn free_vars(&self) -> Vec<Var> {
+        self.matrix
+            .vars()
+            .into_iter()
+            .filter(|var| !self.prefix.iter().any(|(_, block)| block.contains(var)))
+            .collect()
+    }
+
+    /// The prefix with free variables in an outermost existential block, and without empty or repeated blocks.
+    fn normalised_prefix(&self) -> Vec<(Quantifier, Vec<Var>)> {
+        let mut prefix: Vec<(Quantifier, Vec<Var>)> = Vec::new();
+
+        let free = (Quantifier::Exists, self.free_vars());
+
+        for (quantifier, block) in std::iter::once(&free).chain(&self.prefix) {
+            if block.is_empty() {
+                continue;
+            }
+
+            match prefix.last_mut() {
+                Some((last, vars)) if last == quantifier => vars.extend(block),
+                _ => prefix.push((*quantifier, block.clone())),
+            }
+        }
+
+        prefix
+    }
+
+    /// The matrix with its parity and cardinality constraints replaced by clauses, and `prefix` with a block binding the auxiliary variables of the encoding.
+    ///
+    /// Auxiliary variables are defined by the variables of their constraint, so they are existential and innermost.
+    pub(crate) fn clausal(
+        &self,
+        mut prefix: Vec<(Quantifier, Vec<Var>)>,
+    ) -> (Vec<(Quantifier, Vec<Var>)>, Formula) {
+        if self.matrix.is_cnf() {
+            return (prefix, self.matrix.clone());
+        }
+
+        let max_var = prefix
+            .iter()
+            .flat_map(|(_, block)| block.iter().copied())
+            .chain(self.matrix.max_var())
+            .max();
+        let matrix = self.matrix.to_cnf_with(&mut VarPool::after(max_var));
+        let aux: Vec<Var> = matrix
+            .vars()
+            .into_iter()
+            .filter(|var| Some(*var) > max_var)
+            .collect();
+
+        if !aux.is_empty() {
+            match prefix.last_mut() {
+                Some((Quantifier::Exists, block)) => block.extend(aux),
+                _ => prefix.push((Quantifier::Exists, aux)),
+            }
+        }
+
+        (prefix, matrix)
+    }
+}
+
+/// The outcome of `solve_qbf`.
+#[derive(Debug, Clone)]
+pub enum QbfResult {
+    /// The formula is true, with the winning assignment to the outermost block if it is existential.
+    True(Option<Assignment>),
+    /// The formula is false, with the winning assignment to the outermost block if it is universal.
+    False(Option<Assignment>),
+}
+
+impl QbfResult {
+    pub fn is_true(&self) -> bool {
+        matches!(self, QbfResult::True(_))
+    }
+
+    /// The assignment to the outermost block with which its player wins, if that player wins.
+    ///
+    /// An existential certificate makes the rest of the formula true, and a universal one makes it false.
+    pub fn certificate(&self) -> Option<&Assignment> {
+        match self {
+            QbfResult::True(certificate) | QbfResult::False(certificate) => certificate.as_ref(),
+        }
+    }
+}
+
+/// Decides `qbf` by counterexample-guided abstraction refinement with expansion, as in RAReQS.
+///
+/// The certificate, if any, assigns every variable of the outermost block after free variables are moved into an outermost existential block.
+pub fn solve_qbf(qbf: &Qbf) -> QbfResult {
+    let prefix = qbf.normalised_prefix();
+    let max_var = prefix
+        .iter()
+        .flat_map(|(_, block)| block.iter().copied())
+        .max();
+    let (prefix, matrix) = qbf.clausal(prefix);
+    let matrix = Circuit::from_formula(&matrix);
+    let mut pool = VarPool::after(
+        prefix
+            .iter()
+            .flat_map(|(_, block)| block.iter().copied())
+            .max(),
+    );
+
+    let Some((outermost, _)) = prefix.first() else {
+        // Without variables the matrix is constant
+        return match matrix {
+            Circuit::Const(false) => QbfResult::False(None),
+            _ => QbfResult::True(None),
+        };
+    };
+
+    // The outermost block may also bind auxiliary variables, if it is the only one
+    let certificate =
+        rareqs(&prefix, &matrix, &mut pool).map(|certificate| certificate.up_to(max_var));
+
+    match (outermost, certificate) {
+        (Quantifier::Exists, Some(certificate)) => QbfResult::True(Some(certificate)),
+        (Quantifier::Exists, None) => QbfResult::False(None),
+        (Quantifier::Forall, Some(certificate)) => QbfResult::False(Some(certificate)),
+        (Quantifier::Forall, None) => QbfResult::True(None),
+    }
+}
+
+/// Finds a move for the player of the outermost block that wins against every move of the opponent, or `None` if there is none.
+///
+/// The existential player wins when the matrix is true, and the universal player when it is false.
+/// Every variable of `matrix` must be bound in `prefix`, which must be non-empty and alternate.
+fn rareqs(
+    prefix: &[(Quantifier, Vec<Var>)],
+    matrix: &Circuit,
+    pool: &mut VarPool,
+) -> Option<Assignment> {
+    let (quantifier, vars) = &prefix[0];
+
+    if let Circuit::Const(value) = matrix {
+        let wins = *value == (*quantifier == Quantifier::Exists);
+        return wins.then(|| Assignment::new().completed(vars));
+    }
+
+    let Some((opponent, rest)) = prefix[1..].split_first() else {
+        let goal = match quantifier {
+            Quantifier::Exists => matrix.clone(),
+            Quantifier::Forall => matrix.negate(),
+        };
+
+        return satisfy(&goal).map(|model| model.completed(vars));
+    };
+
+    // The abstraction requires the proposal to win against every counter-move seen so far
+    let mut abstraction_prefix = vec![(*quantifier, vars.clone())];
+    let mut copies = Vec::new();
+
+    loop {
+        let abstraction = match quantifier {
+            Quantifier::Exists => Circuit::and(copies.clone()),
+            Quantifier::Forall => Circuit::or(copies.clone()),
+        };
+
+        let proposal = rareqs(&abstraction_prefix, &abstraction, pool)?.completed(vars);
+
+        let Some(counter) = rareqs(&prefix[1..], &matrix.substitute(&proposal), pool) else {
+            return Some(proposal);
+        };
+
+        // Expands the rest of the formula on the counter-move, with fresh copies of the inner variables
+        let mut renaming = HashMap::new();
+
+        for (depth, (inner, block)) in rest.iter().enumerate() {
+            let copy: Vec<Var> = block
+                .iter()
+                .map(|var| *renaming.entry(*var).or_insert_with(|| pool.fresh()))
+                .collect();
+
+            // Blocks after the opponent's alternate from the proposer's quantifier, so the first joins the proposer's block
+            debug_assert_eq!(*inner == *quantifier, depth % 2 == 0);
+
+            match abstraction_prefix.get_mut(depth) {
+                Some((_, vars)) => vars.extend(copy),
+                None => abstraction_prefix.push((*inner, copy)),
+            }
+        }
+
+        copies.push(
+            matrix
+                .substitute(&counter.completed(&opponent.1))
+                .rename(&renaming),
+        );
+    }
+}
+
+/// Finds an assignment making `circuit` true, through its Plaisted-Greenbaum encoding as clauses.
+fn satisfy(circuit: &Circuit) -> Option<Assignment> {
+    let mut pool = VarPool::after(circuit.max_var());
+    let mut formula = Formula::new();
+
+    match circuit {
+        Circuit::Const(true) => return Some(Assignment::new()),
+        Circuit::Const(false) => return None,
+        _ => {
+            let root = circuit.encode(&mut pool, &mut formula);
+            formula.add_clause(vec![root].into());
+        }
+    }
+
+    match Solver::from_formula(&formula, SolverConfig::default()).solve() {
+        SolveResult::Sat(model) => Some(model),
+        _ => None,
+    }
+}
+
+/// A negation-free Boolean circuit over literals.
+#[derive(Debug, Clone)]
+enum Circuit {
+    Const(bool),
+    Lit(Lit),
+    And(Vec<Circuit>),
+    Or(Vec<Circuit>),
+}
+
+impl Circuit {
+    fn from_formula(formula: &Formula) -> Circuit {
+        Circuit::and(
+            formula
+                .clauses()
+                .iter()
+                .map(|clause| {
+                    Circuit::or(clause.literals().into_iter().map(Circuit::Lit).collect())
+                })
+                .collect(),
+        )
+    }
+
+    /// The conjunction of `children`, simplifying constants.
+    fn and(children: Vec<Circuit>) -> Circuit {
+        Circuit::join(children, true)
+    }
+
+    /// The disjunction of `children`, simplifying constants.
+    fn or(children: Vec<Circuit>) -> Circuit {
+        Circuit::join(children, false)
+    }
+
+    /// A conjunction if `and` is `true`, otherwise a disjunction, whose identity is `and`.
+    fn join(children: Vec<Circuit>, and: bool) -> Circuit {
+        let mut kept = Vec::with_capacity(children.len());
+
+        for child in children {
+            match child {
+                Circuit::Const(value) if value == and => continue,
+                Circuit::Const(_) => return Circuit::Const(!and),
+                child => kept.push(child),
+            }
+        }
+
+        match kept.len() {
+            0 => Circuit::Const(and),
+            1 => kept.pop().unwrap(),
+            _ if and => Circuit::And(kept),
+            _ => Circuit::Or(kept),
+        }
+    }
+
+    /// Replaces the variables assigned in `assignment` by their values.
+    fn substitute(&self, assignment: &Assignment) -> Circuit {
+        match self {
+            Circuit::Const(value) => Circuit::Const(*value),
+            Circuit::Lit(lit) => match assignment.evaluate(lit) {
+                Some(value) => Circuit::Const(value),
+                None => Circuit::Lit(*lit),
+            },
+            Circuit::And(children) => Circuit::and(
+                children
+                    .iter()
+                    .map(|child| child.substitute(assignment))
+                    .collect(),
+            ),
+            Circuit::Or(children) => Circuit::or(
+                children
+                    .iter()
+                    .map(|child| child.substitute(assignment))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Replaces variables by their images in `renaming`.
+    fn rename(&self, renaming: &HashMap<Var, Var>) -> Circuit {
+        match self {
+            Circuit::Const(value) => Circuit::Const(*value),
+            Circuit::Lit(lit) => match renaming.get(&lit.var()) {
+                Some(var) => Circuit::Lit(Lit::from_var(var, lit.polarity())),
+                None => Circuit::Lit(*lit),
+            },
+            Circuit::And(children) => Circuit::And(
+                children
+                    .iter()
+                    .map(|child| child.rename(renaming))
+                    .collect(),
+            ),
+            Circuit::Or(children) => Circuit::Or(
+                children
+                    .iter()
+                    .map(|child| child.rename(renaming))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// The complement of this circuit, with negations pushed to the literals.
+    fn negate(&self) -> Circuit {
+        match self {
+            Circuit::Const(value) => Circuit::Const(!value),
+            Circuit::Lit(lit) => Circuit::Lit(!*lit),
+            Circuit::And(children) => Circuit::Or(children.iter().map(Circuit::negate).collect()),
+            Circuit::Or(children) => Circuit::And(children.iter().map(Circuit::negate).collect()),
+        }
+    }
+
+    fn max_var(&self) -> Option<Var> {
+        match self {
+            Circuit::Const(_) => None,
+            Circuit::Lit(lit) => Some(lit.var()),
+            Circuit::And(children) | Circuit::Or(children) => {
+                children.iter().filter_map(Circuit::max_var).max()
+            }
+        }
+    }
+
+    /// Adds clauses to `formula` such that the returned literal implies this circuit, taking a fresh variable for each gate.
+    ///
+    /// Only one direction of each gate is needed, since the circuit has no negations.
+    fn encode(&self, pool: &mut VarPool, formula: &mut Formula) -> Lit {
+        match self {
+            Circuit::Lit(lit) => *lit,
+            Circuit::Const(_) => unreachable!("constants are simplified out of gates"),
+            Circuit::And(children) => {
+                let gate = pool.fresh().positive();
+
+                for child in children {
+                    let child = child.encode(pool, formula);
+                    formula.add_clause(vec![!gate, child].into());
+                }
+
+                gate
+            }
+            Circuit::Or(children) => {
+                let gate = pool.fresh().positive();
+                let mut clause = vec![!gate];
+
+                for child in children {
+                    clause.push(child.encode(pool, formula));
+                }
+
+                formula.add_clause(clause.into());
+                gate
+            }
+        }
+    }
+}
@@ -0,0 +1,177 @@
+//! Solution sampling
+//!
+//! Draws models of a formula at random, for constrained random test generation.
+//! The hashing sampler follows UniGen: random XOR constraints split the models into small cells, and a model is picked uniformly from a random cell.
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{
+    approx::{add_hashes, random_hash, Hash},
+    approx_count_projected_models, projected_models, solve_with_assumptions, Assignment, Formula,
+    Lit, Var,
+};
+
+/// Controls the tolerance of the hashing sampler, as in UniGen.
+const KAPPA: f64 = 0.638;
+
+/// Random hash partitions tried for one sample before the hashing sampler gives up.
+///
+/// UniGen shows each attempt succeeds with probability above a half, so giving up is very unlikely unless the estimated count was far off.
+const MAX_HASH_ATTEMPTS: usize = 32;
+
+/// How samples are drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SamplingStrategy {
+    /// Picks a random model from a random cell of a hash partition of the models.
+    /// Near-uniform, but each sample enumerates a few cells.
+    Hashing,
+    /// Fixes the variables in a random order, each to a random value if the formula stays satisfiable.
+    /// Needs at most one solve per variable, but is not uniform.
+    RandomPolarity,
+}
+
+/// An infinite stream of random models of a formula, or an empty one if it is unsatisfiable.
+///
+/// Each item assigns exactly the variables of the formula. The same seed always gives the same stream.
+/// The hashing sampler ends the stream early if several random partitions in a row all fail to give a cell of the right size.
+pub struct Samples<'a> {
+    formula: &'a Formula,
+    vars: Vec<Var>,
+    strategy: SamplingStrategy,
+    rng: StdRng,
+    /// Computed before the first hashed sample.
+    cells: Option<Cells>,
+}
+
+/// How the hashing sampler partitions the models.
+enum Cells {
+    Unsatisfiable,
+    /// There are few enough models to choose between them directly.
+    Exact(Vec<Assignment>),
+    /// Each sample tries adding between `min` and `max` hashes, until one selects a cell of the right size.
+    Hashed {
+        min: usize,
+        max: usize,
+    },
+}
+
+/// Draws random models of `formula` using the given strategy.
+pub fn samples(formula: &Formula, strategy: SamplingStrategy, seed: u64) -> Samples<'_> {
+    Samples {
+        formula,
+        vars: formula.vars(),
+        strategy,
+        rng: StdRng::seed_from_u64(seed),
+        cells: None,
+    }
+}
+
+impl Iterator for Samples<'_> {
+    type Item = Assignment;
+
+    fn next(&mut self) -> Option<Assignment> {
+        match self.strategy {
+            SamplingStrategy::Hashing => self.hashing_sample(),
+            SamplingStrategy::RandomPolarity => self.polarity_sample(),
+        }
+    }
+}
+
+impl Samples<'_> {
+    fn hashing_sample(&mut self) -> Option<Assignment> {
+        let pivot = (4.03 * (1.0 + 1.0 / KAPPA).powi(2)).ceil();
+        let high = (1.0 + (1.0 + KAPPA) * pivot) as usize;
+        let low = (pivot / (1.0 + KAPPA)) as usize;
+
+        let cells = match self.cells.take() {
+            Some(cells) => cells,
+            None => self.partition(pivot, high),
+        };
+
+        let sample = match &cells {
+            Cells::Unsatisfiable => None,
+            Cells::Exact(models) => models.get(self.rng.random_range(0..models.len())).cloned(),
+            Cells::Hashed { min, max } => (0..MAX_HASH_ATTEMPTS).find_map(|_| {
+                let hashes: Vec<_> = (0..*max)
+                    .map(|_| random_hash(&self.vars, &mut self.rng))
+                    .collect();
+
+                let cell = (*min..=*max)
+                    .map(|count| self.cell(&hashes[..count], high))
+                    .find(|cell| (low..=high).contains(&cell.len()))?;
+
+                Some(cell[self.rng.random_range(0..cell.len())].clone())
+            }),
+        };
+
+        self.cells = Some(cells);
+
+        sample
+    }
+
+    /// Decides how many hashes to add so that cells hold around `pivot` models.
+    fn partition(&mut self, pivot: f64, high: usize) -> Cells {
+        let models = self.cell(&[], high);
+
+        if models.is_empty() {
+            return Cells::Unsatisfiable;
+        }
+
+        if models.len() <= high {
+            return Cells::Exact(models);
+        }
+
+        let estimate =
+            approx_count_projected_models(self.formula, &self.vars, 0.8, 0.2, self.rng.random())
+                .to_f64();
+        let max = ((estimate.log2() + 1.8f64.log2() - pivot.log2()).ceil() as usize)
+            .clamp(1, self.vars.len());
+
+        Cells::Hashed {
+            min: max.saturating_sub(3).max(1),
+            max,
+        }
+    }
+
+    /// Enumerates up to `limit + 1` models in the cell selected by `hashes`, in a fixed order.
+    fn cell(&self, hashes: &[Hash], limit: usize) -> Vec<Assignment> {
+        let hashed = add_hashes(self.formula, &self.vars, hashes);
+        let mut models: Vec<Assignment> = projected_models(&hashed, &self.vars)
+            .take(limit + 1)
+            .collect();
+
+        // Enumeration order depends on the solver, so sort to keep samples reproducible
+        models.sort_by_key(|model| model.lits());
+
+        models
+    }
+
+    fn polarity_sample(&mut self) -> Option<Assignment> {
+        let mut model = solve_with_assumptions(self.formula, &[])?.completed(&self.vars);
+
+        let mut order = self.vars.clone();
+        order.shuffle(&mut self.rng);
+
+        let mut assumptions = Vec::with_capacity(order.len());
+
+        for var in order {
+            let mut lit = Lit::from_var(&var, self.rng.random_bool(0.5));
+
+            // The current model witnesses that `lit` is consistent, so only solve when it disagrees
+            if model.evaluate(&lit) != Some(true) {
+                assumptions.push(lit);
+
+                match solve_with_assumptions(self.formula, &assumptions) {
+                    Some(found) => model = found.completed(&self.vars),
+                    None => lit = !lit,
+                }
+
+                assumptions.pop();
+            }
+
+            assumptions.push(lit);
+        }
+
+        Some(model)
+    }
+}
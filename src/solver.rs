@@ -1,6 +1,12 @@
 use std::collections::HashSet;
 
-use crate::{Assignment, Formula, Lit, Var};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+
+use crate::{
+    cube_and_conquer, formula::clausal_beside, is_dual_horn, is_horn, local_search,
+    lookahead_solve, solve_2sat, solve_dual_horn, solve_horn, solve_portfolio, Assignment, Formula,
+    Lit, LocalSearchConfig, PortfolioConfig, Solver, SolverConfig, Var,
+};
 
 #[derive(Debug, Clone)]
 enum ClauseState {
@@ -26,7 +32,7 @@ struct Context<'a> {
 }
 
 impl<'a> Context<'a> {
-    pub fn new(formula: &Formula) -> Context {
+    pub fn new(formula: &Formula) -> Context<'_> {
         let mut unassigned_variables = HashSet::new();
         let mut clause_states = Vec::new();
         let mut unit_lits = Vec::new();
@@ -140,7 +146,7 @@ impl<'a> Context<'a> {
             }
         }
 
-        return false;
+        false
     }
 
     /// Shortcut for `assign(lit.var(), lit.polarity())`.
@@ -153,7 +159,7 @@ impl<'a> Context<'a> {
 
     /// Gets a unit literal if one exists.
     pub fn get_unit_lit(&self) -> Option<Lit> {
-        self.unit_lits.get(0).copied()
+        self.unit_lits.first().copied()
     }
 
     /// Tries to get an unassigned variable.
@@ -176,12 +182,119 @@ impl<'a> Context<'a> {
     }
 }
 
+/// The outcome of running a solver on a formula.
+#[derive(Debug, Clone)]
+pub enum SolveResult {
+    /// The formula is satisfied by this assignment.
+    Sat(Assignment),
+    /// The formula has no satisfying assignment.
+    Unsat,
+    /// The solver gave up, for example by reaching a limit.
+    Unknown,
+}
+
+impl SolveResult {
+    /// Gets the satisfying assignment, if one was found.
+    pub fn model(&self) -> Option<&Assignment> {
+        match self {
+            SolveResult::Sat(assignment) => Some(assignment),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the formula was found to be satisfiable.
+    pub fn is_sat(&self) -> bool {
+        matches!(self, SolveResult::Sat(_))
+    }
+
+    /// Returns `true` if the formula was proven unsatisfiable.
+    pub fn is_unsat(&self) -> bool {
+        matches!(self, SolveResult::Unsat)
+    }
+}
+
+/// A solving engine, chosen at runtime with `solve_with`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Engine {
+    /// The complete DPLL search used by `solve`.
+    Dpll,
+    /// Incomplete stochastic local search, see `local_search`.
+    LocalSearch(LocalSearchConfig),
+    /// A conflict-driven clause learning `Solver`.
+    Cdcl(SolverConfig),
+    /// Several CDCL solvers in parallel, see `solve_portfolio`.
+    Portfolio(PortfolioConfig),
+    /// Lookahead cubes of up to `depth` decisions solved in parallel, see `cube_and_conquer`.
+    CubeAndConquer { depth: usize },
+    /// DPLL branching on lookahead variables, see `lookahead_solve`.
+    Lookahead,
+}
+
+/// Solves `formula` with the given engine.
+///
+/// Unlike `solve`, an empty formula is satisfiable.
+pub fn solve_with(formula: &Formula, engine: &Engine) -> SolveResult {
+    match engine {
+        Engine::Dpll => match solve_with_assumptions(formula, &[]) {
+            Some(assignment) => SolveResult::Sat(assignment),
+            None => SolveResult::Unsat,
+        },
+        Engine::LocalSearch(config) => local_search(formula, config),
+        Engine::Cdcl(config) => Solver::from_formula(formula, config.clone()).solve(),
+        Engine::Portfolio(config) => solve_portfolio(formula, config),
+        Engine::CubeAndConquer { depth } => cube_and_conquer(formula, *depth),
+        Engine::Lookahead => lookahead_solve(formula),
+    }
+}
+
 /// Attempts to find a satisfying set of assignments for this formula. Variables not in the returned solution are unassigned and can take any value.
+/// `Assignment::shrink` drops further variables from a solution while keeping it satisfying.
+///
+/// Formulas that split into independent components, see `Formula::components`, have each component solved in parallel.
+/// Formulas with at most two literals per clause are solved in linear time by `solve_2sat`, and Horn and dual-Horn formulas by `solve_horn` and `solve_dual_horn`.
+/// Formulas with parity or cardinality constraints are solved by a CDCL `Solver`.
+/// Other solvers that do not support these constraints natively replace them by clauses, see `Formula::to_cnf`.
 pub fn solve(formula: &Formula) -> Option<Assignment> {
-    if formula.clauses().is_empty() {
+    if formula.clauses().is_empty() && formula.is_cnf() {
         return None;
     }
 
+    let components = formula.components();
+
+    if components.len() > 1 {
+        // Collecting into an `Option` stops at the first unsatisfiable component
+        let solutions: Option<Vec<Assignment>> = components.par_iter().map(solve).collect();
+        let mut assignment = Assignment::new();
+
+        for solution in solutions? {
+            for (var, value) in solution.vec() {
+                assignment.set(var, value);
+            }
+        }
+
+        return Some(assignment);
+    }
+
+    // Parity and cardinality constraints are propagated natively by the CDCL solver
+    if !formula.is_cnf() {
+        return Solver::from_formula(formula, SolverConfig::default())
+            .solve()
+            .model()
+            .cloned();
+    }
+
+    if formula.clauses().iter().all(|clause| clause.len() <= 2) {
+        return solve_2sat(formula);
+    }
+
+    if is_horn(formula) {
+        return solve_horn(formula);
+    }
+
+    if is_dual_horn(formula) {
+        return solve_dual_horn(formula);
+    }
+
     let solution = attempt_solve(Context::new(formula));
 
     if let Some(solution) = &solution {
@@ -193,6 +306,69 @@ pub fn solve(formula: &Formula) -> Option<Assignment> {
     solution
 }
 
+/// Attempts to find a satisfying assignment in which every literal in `assumptions` is true.
+///
+/// Unlike `solve`, an empty formula is satisfiable, and the returned assignment always contains the assumptions.
+pub fn solve_with_assumptions(formula: &Formula, assumptions: &[Lit]) -> Option<Assignment> {
+    let (clausal, max_var) = clausal_beside(formula, assumptions.iter().map(|lit| lit.var()));
+
+    // Watching the same literal twice breaks propagation, and a formula without variables never reaches an empty clause
+    let mut normalised = Formula::new();
+
+    for clause in clausal.clauses() {
+        let mut literals = clause.literals();
+        literals.sort();
+        literals.dedup();
+
+        if literals.is_empty() {
+            return None;
+        }
+
+        normalised.add_clause(literals.into());
+    }
+
+    let mut ctx = Context::new(&normalised);
+
+    for lit in assumptions {
+        match ctx.assignment.evaluate(lit) {
+            Some(true) => continue,
+            Some(false) => return None,
+            None => {
+                if ctx.assign_lit(lit) {
+                    return None;
+                }
+            }
+        }
+    }
+
+    attempt_solve(ctx).map(|assignment| assignment.up_to(max_var))
+}
+
+/// Finds a subset of `assumptions` that cannot all be true in any model of `formula`.
+///
+/// Returns `None` if the formula is satisfiable under the assumptions. The core is minimal: removing any one of its literals makes the formula satisfiable.
+pub fn unsat_core(formula: &Formula, assumptions: &[Lit]) -> Option<Vec<Lit>> {
+    if solve_with_assumptions(formula, assumptions).is_some() {
+        return None;
+    }
+
+    let mut core = assumptions.to_vec();
+    let mut i = 0;
+
+    while i < core.len() {
+        let mut candidate = core.clone();
+        candidate.remove(i);
+
+        if solve_with_assumptions(formula, &candidate).is_none() {
+            core = candidate;
+        } else {
+            i += 1;
+        }
+    }
+
+    Some(core)
+}
+
 /// Continues a DPLL solve using known assignments and an assumed value.
 fn attempt_solve(mut ctx: Context) -> Option<Assignment> {
     if bcp(&mut ctx) {
@@ -0,0 +1,137 @@
+//! Subsumption and self-subsuming resolution
+//!
+//! A clause subsumes another if its literals are a subset of the other's, making the other redundant.
+//! If the subset holds except for one literal appearing negated, resolving the two removes that literal from the larger clause.
+
+use crate::{preprocess::ClauseDb, Formula, Lit};
+
+/// Counters describing the simplifications made by subsumption.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SubsumptionStats {
+    /// Clauses removed because another clause subsumed them.
+    pub removed_clauses: usize,
+    /// Literals removed from clauses by self-subsuming resolution.
+    pub removed_literals: usize,
+}
+
+/// Removes subsumed clauses and strengthens clauses by self-subsuming resolution until neither applies, or the empty clause is derived.
+///
+/// The simplified formula has exactly the same models as `formula`.
+pub fn simplify(formula: &Formula) -> (Formula, SubsumptionStats) {
+    let mut db = ClauseDb::new(formula);
+    let mut stats = SubsumptionStats::default();
+
+    subsume_all(&mut db, &mut stats);
+
+    (db.to_formula(), stats)
+}
+
+/// Uses every clause to subsume or strengthen the others, smallest first.
+pub(crate) fn subsume_all(db: &mut ClauseDb, stats: &mut SubsumptionStats) {
+    let mut queue = db.indices();
+    queue.sort_by_key(|index| std::cmp::Reverse(db.get(*index).unwrap().len()));
+
+    while let Some(index) = queue.pop() {
+        let Some(lits) = db.get(index).cloned() else {
+            continue;
+        };
+
+        // Every clause this one can subsume or strengthen contains each of its variables, so check the rarest
+        let Some(var) = lits.iter().map(|lit| lit.var()).min_by_key(|var| {
+            db.occurrences(var.positive()).len() + db.occurrences(var.negative()).len()
+        }) else {
+            continue;
+        };
+
+        let mut candidates = db.occurrences(var.positive());
+        candidates.extend(db.occurrences(var.negative()));
+
+        for other in candidates {
+            if other == index || db.signature(index) & !db.signature(other) != 0 {
+                continue;
+            }
+
+            let Some(other_lits) = db.get(other) else {
+                continue;
+            };
+
+            match subset_except_one(&lits, other_lits) {
+                Some(None) => {
+                    db.remove(other);
+                    stats.removed_clauses += 1;
+                }
+                Some(Some(lit)) => {
+                    db.strengthen(other, lit);
+                    stats.removed_literals += 1;
+
+                    if db.is_unsat() {
+                        return;
+                    }
+
+                    // The shorter clause may now subsume others, and be subsumed or strengthened by clauses already used
+                    queue.push(other);
+                    queue.extend(acting_on(db, other));
+                }
+                None => {}
+            }
+        }
+    }
+}
+
+/// Clauses other than `index` whose variables all occur in it, which could subsume or strengthen it.
+fn acting_on(db: &ClauseDb, index: usize) -> Vec<usize> {
+    let Some(lits) = db.get(index) else {
+        return Vec::new();
+    };
+
+    let Some(var) = lits.iter().map(|lit| lit.var()).min_by_key(|var| {
+        db.occurrences(var.positive()).len() + db.occurrences(var.negative()).len()
+    }) else {
+        return Vec::new();
+    };
+
+    let mut candidates = db.occurrences(var.positive());
+    candidates.extend(db.occurrences(var.negative()));
+    candidates.retain(|other| *other != index && db.signature(*other) & !db.signature(index) == 0);
+
+    candidates
+}
+
+/// Checks whether a clause containing `lits` would be subsumed by a clause in `db`.
+pub(crate) fn is_subsumed(db: &ClauseDb, lits: &[Lit]) -> bool {
+    let sig = crate::preprocess::signature(lits);
+
+    lits.iter().any(|lit| {
+        db.occurrences(*lit).into_iter().any(|index| {
+            db.signature(index) & !sig == 0
+                && db
+                    .get(index)
+                    .is_some_and(|other| other.iter().all(|lit| lits.contains(lit)))
+        })
+    })
+}
+
+/// Checks whether `small` is a subset of `large`, allowing at most one literal of `small` to appear negated.
+///
+/// Returns `Some(None)` if `small` subsumes `large`, and `Some(Some(lit))` if `lit` in `large` can be removed by resolving with `small`.
+fn subset_except_one(small: &[Lit], large: &[Lit]) -> Option<Option<Lit>> {
+    if small.len() > large.len() {
+        return None;
+    }
+
+    let mut flipped = None;
+
+    for lit in small {
+        if large.contains(lit) {
+            continue;
+        }
+
+        if flipped.is_none() && large.contains(&!*lit) {
+            flipped = Some(!*lit);
+        } else {
+            return None;
+        }
+    }
+
+    Some(flipped)
+}
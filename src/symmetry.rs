@@ -0,0 +1,393 @@
+//! Symmetry detection and static symmetry breaking
+//!
+//! A symmetry of a formula is a permutation of its literals, commuting with negation, that maps its set of clauses onto itself, and so maps models to models.
+//! Symmetries are found as automorphisms of a coloured graph with a vertex for each literal and each clause, using a built-in search that refines colourings and individualises vertices, in the style of nauty and saucy.
+//! Breaking a symmetry adds a lex-leader predicate, which only allows models that are no greater than their image in a fixed variable order.
+//! Every orbit of models keeps its least member, so the formula stays equisatisfiable.
+
+use crate::{formula::clausal, Clause, Formula, Lit, Var, VarPool};
+
+/// Search nodes visited before automorphism search gives up, returning the symmetries found so far.
+const MAX_SEARCH_NODES: usize = 100_000;
+
+/// A permutation of literals that maps the clauses of a formula onto themselves.
+///
+/// The image of `!lit` is always the complement of the image of `lit`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symmetry {
+    /// The image of the positive literal of each variable, indexed by `Var::index`.
+    images: Vec<Lit>,
+}
+
+impl Symmetry {
+    /// The image of `lit`.
+    pub fn apply(&self, lit: Lit) -> Lit {
+        match self.images.get(lit.index()) {
+            Some(image) if lit.is_positive() => *image,
+            Some(image) => !*image,
+            None => lit,
+        }
+    }
+
+    /// The variables this symmetry moves, sorted by index.
+    pub fn support(&self) -> Vec<Var> {
+        self.images
+            .iter()
+            .enumerate()
+            .filter(|(index, image)| image.index() != *index || image.is_negative())
+            .map(|(index, _)| Var::from_index(index).unwrap())
+            .collect()
+    }
+}
+
+/// Finds symmetries of `formula` that generate its group of symmetries.
+///
+/// Parity and cardinality constraints are replaced by clauses first, and only symmetries that map their auxiliary variables among themselves are found.
+/// If the search reaches its limit, the symmetries found so far generate a subgroup.
+pub fn symmetries(formula: &Formula) -> Vec<Symmetry> {
+    let max_var = formula.max_var();
+    let formula = clausal(formula);
+    let vars = formula.vars();
+    let graph = Graph::new(&formula, &vars, max_var);
+    let mut budget = MAX_SEARCH_NODES;
+
+    let images_len = max_var.map_or(0, |var| var.index() + 1);
+
+    graph
+        .automorphisms(&mut budget)
+        .into_iter()
+        .filter_map(|permutation| {
+            let mut images: Vec<Lit> = (0..images_len)
+                .map(|index| Var::from_index(index).unwrap().positive())
+                .collect();
+
+            // Literal vertex `2 * k` is the positive literal of `vars[k]`, and `2 * k + 1` its complement
+            for (k, var) in vars
+                .iter()
+                .enumerate()
+                .take_while(|(_, var)| Some(**var) <= max_var)
+            {
+                let image = permutation[2 * k];
+                images[var.index()] = Lit::from_var(&vars[image / 2], image % 2 == 0);
+            }
+
+            let symmetry = Symmetry { images };
+
+            // Permutations of duplicate clause vertices alone move no literals
+            (!symmetry.support().is_empty()).then_some(symmetry)
+        })
+        .collect()
+}
+
+/// Returns `formula` with a lex-leader predicate for each of its symmetries, see `symmetries`.
+///
+/// The result is satisfiable exactly when `formula` is, and every model of it, restricted to the variables of `formula`, is a model of `formula`.
+pub fn break_symmetries(formula: &Formula) -> Formula {
+    let mut broken = formula.clone();
+    let mut pool = VarPool::after(formula.max_var());
+
+    for clause in symmetry_breaking_clauses(formula, &mut pool).1 {
+        broken.add_clause(clause);
+    }
+
+    broken
+}
+
+/// Finds the symmetries of `formula` and encodes a lex-leader predicate for each, taking auxiliary variables from `pool`.
+///
+/// Returns the number of symmetries and the clauses of the predicates.
+pub(crate) fn symmetry_breaking_clauses(
+    formula: &Formula,
+    pool: &mut VarPool,
+) -> (usize, Vec<Clause>) {
+    let symmetries = symmetries(formula);
+    let mut clauses = Vec::new();
+
+    for symmetry in &symmetries {
+        lex_leader(symmetry, pool, &mut clauses);
+    }
+
+    (symmetries.len(), clauses)
+}
+
+/// Encodes that the assignment to the support of `symmetry`, in index order, is lexicographically no greater than its image, with `false` before `true`.
+///
+/// Auxiliary literal `equal_i` is forced true while the first `i` variables equal their images, and then the next variable must be no greater than its image.
+fn lex_leader(symmetry: &Symmetry, pool: &mut VarPool, clauses: &mut Vec<Clause>) {
+    let support = symmetry.support();
+    let mut equal: Option<Lit> = None;
+
+    for (i, var) in support.iter().enumerate() {
+        let lit = var.positive();
+        let image = symmetry.apply(lit);
+        let guard: Vec<Lit> = equal.iter().map(|equal| !*equal).collect();
+
+        // A variable mapped to its own complement is only no greater than its image when false
+        let mut less = guard.clone();
+        less.push(!lit);
+
+        if image != !lit {
+            less.push(image);
+        }
+
+        clauses.push(less.into());
+
+        if i + 1 == support.len() {
+            break;
+        }
+
+        let next = pool.fresh().positive();
+
+        for lits in [[!lit, next], [image, next]] {
+            let mut clause = guard.clone();
+            clause.extend(lits);
+            clauses.push(clause.into());
+        }
+
+        equal = Some(next);
+    }
+}
+
+/// An undirected graph with coloured vertices, whose automorphisms are the symmetries of a formula.
+struct Graph {
+    /// Sorted neighbours of each vertex.
+    adjacency: Vec<Vec<usize>>,
+    colours: Vec<usize>,
+}
+
+impl Graph {
+    /// Builds the graph of the clauses of `formula` over `vars`, its sorted variables, of which those after `max_var` are auxiliary.
+    ///
+    /// Each variable has two literal vertices joined by an edge, followed by one vertex for each distinct clause, joined to its literals.
+    /// Literal and clause vertices have different colours, so automorphisms map literals to literals and commute with negation.
+    /// Auxiliary literals have a colour of their own, so automorphisms map the other variables among themselves.
+    fn new(formula: &Formula, vars: &[Var], max_var: Option<Var>) -> Graph {
+        let vertex = |lit: &Lit| {
+            let k = vars.binary_search(&lit.var()).unwrap();
+            2 * k + lit.is_negative() as usize
+        };
+
+        let mut clauses: Vec<Vec<usize>> = formula
+            .clauses()
+            .iter()
+            .map(|clause| {
+                let mut vertices: Vec<usize> = clause.literals().iter().map(vertex).collect();
+                vertices.sort();
+                vertices.dedup();
+                vertices
+            })
+            .filter(|vertices| !vertices.windows(2).any(|pair| pair[0] / 2 == pair[1] / 2))
+            .collect();
+
+        clauses.sort();
+        clauses.dedup();
+
+        let literals = 2 * vars.len();
+        let mut adjacency = vec![Vec::new(); literals + clauses.len()];
+
+        for k in 0..vars.len() {
+            adjacency[2 * k].push(2 * k + 1);
+            adjacency[2 * k + 1].push(2 * k);
+        }
+
+        for (i, vertices) in clauses.iter().enumerate() {
+            for lit in vertices {
+                adjacency[literals + i].push(*lit);
+                adjacency[*lit].push(literals + i);
+            }
+        }
+
+        for neighbours in &mut adjacency {
+            neighbours.sort();
+        }
+
+        let colours = (0..adjacency.len())
+            .map(|v| match v {
+                _ if v >= literals => 1,
+                _ if Some(vars[v / 2]) > max_var => 2,
+                _ => 0,
+            })
+            .collect();
+
+        Graph { adjacency, colours }
+    }
+
+    /// Finds automorphisms, as vertex permutations, that generate the automorphism group.
+    ///
+    /// Follows the leftmost path of the search tree, always individualising the lowest vertex of the first non-singleton colour, down to a discrete colouring.
+    /// Then, from the deepest level up, looks for automorphisms mapping that level's vertex to each other vertex of its colour not yet in its orbit.
+    /// Automorphisms found at deeper levels fix every vertex individualised above them, so the orbits at each level are those of the stabiliser of the path so far.
+    fn automorphisms(&self, budget: &mut usize) -> Vec<Vec<usize>> {
+        let mut colours = self.colours.clone();
+        self.refine(&mut colours);
+
+        let mut path = Vec::new();
+
+        while let Some(colour) = target(&colours) {
+            let vertex = colours.iter().position(|c| *c == colour).unwrap();
+            let next = self.individualise(&colours, vertex);
+            path.push((colours, vertex, colour, next.clone()));
+            colours = next;
+        }
+
+        let mut generators = Vec::new();
+        let mut orbits: Vec<usize> = (0..self.adjacency.len()).collect();
+
+        for (colours, vertex, colour, left) in path.iter().rev() {
+            for other in (0..colours.len()).filter(|v| colours[*v] == *colour) {
+                if *budget == 0 {
+                    return generators;
+                }
+
+                if find(&mut orbits, other) == find(&mut orbits, *vertex) {
+                    continue;
+                }
+
+                let right = self.individualise(colours, other);
+
+                if let Some(permutation) = self.search(left, &right, budget) {
+                    for (v, image) in permutation.iter().enumerate() {
+                        let (a, b) = (find(&mut orbits, v), find(&mut orbits, *image));
+                        orbits[a] = b;
+                    }
+
+                    generators.push(permutation);
+                }
+            }
+        }
+
+        generators
+    }
+
+    /// Searches for an automorphism mapping the leaf reached from `left` along the leftmost path to a leaf reached from `right`.
+    fn search(&self, left: &[usize], right: &[usize], budget: &mut usize) -> Option<Vec<usize>> {
+        if *budget == 0 || histogram(left) != histogram(right) {
+            return None;
+        }
+
+        *budget -= 1;
+
+        let Some(colour) = target(left) else {
+            // Both colourings are discrete, so vertices of the same colour correspond
+            let mut by_colour = vec![0; right.len()];
+
+            for (v, c) in right.iter().enumerate() {
+                by_colour[*c] = v;
+            }
+
+            let permutation: Vec<usize> = left.iter().map(|c| by_colour[*c]).collect();
+
+            return self.is_automorphism(&permutation).then_some(permutation);
+        };
+
+        let vertex = left.iter().position(|c| *c == colour).unwrap();
+        let left = self.individualise(left, vertex);
+
+        for other in (0..right.len()).filter(|v| right[*v] == colour) {
+            let right = self.individualise(right, other);
+
+            if let Some(permutation) = self.search(&left, &right, budget) {
+                return Some(permutation);
+            }
+        }
+
+        None
+    }
+
+    /// Gives `vertex` a colour of its own, ordered before the rest of its colour, and refines the result.
+    fn individualise(&self, colours: &[usize], vertex: usize) -> Vec<usize> {
+        let mut colours: Vec<usize> = colours
+            .iter()
+            .enumerate()
+            .map(|(v, c)| 2 * c + (v != vertex) as usize)
+            .collect();
+
+        self.refine(&mut colours);
+
+        colours
+    }
+
+    /// Refines `colours` until every two vertices of the same colour have the same number of neighbours of each colour.
+    ///
+    /// New colours are ranks of an isomorphism-invariant signature, so isomorphic colourings refine to isomorphic colourings with the same colour numbers.
+    fn refine(&self, colours: &mut [usize]) {
+        let mut count = None;
+
+        loop {
+            let signatures: Vec<(usize, Vec<usize>)> = self
+                .adjacency
+                .iter()
+                .enumerate()
+                .map(|(v, neighbours)| {
+                    let mut around: Vec<usize> = neighbours.iter().map(|u| colours[*u]).collect();
+                    around.sort();
+                    (colours[v], around)
+                })
+                .collect();
+
+            let mut ranks: Vec<&(usize, Vec<usize>)> = signatures.iter().collect();
+            ranks.sort();
+            ranks.dedup();
+
+            for (colour, signature) in colours.iter_mut().zip(&signatures) {
+                *colour = ranks.binary_search(&signature).unwrap();
+            }
+
+            if count == Some(ranks.len()) {
+                return;
+            }
+
+            count = Some(ranks.len());
+        }
+    }
+
+    fn is_automorphism(&self, permutation: &[usize]) -> bool {
+        self.adjacency.iter().enumerate().all(|(v, neighbours)| {
+            let image = permutation[v];
+
+            self.colours[v] == self.colours[image]
+                && neighbours.len() == self.adjacency[image].len()
+                && neighbours.iter().all(|u| {
+                    self.adjacency[image]
+                        .binary_search(&permutation[*u])
+                        .is_ok()
+                })
+        })
+    }
+}
+
+/// The lowest colour shared by more than one vertex, or `None` if the colouring is discrete.
+fn target(colours: &[usize]) -> Option<usize> {
+    let counts = histogram(colours);
+
+    counts.iter().position(|count| *count > 1)
+}
+
+/// The number of vertices of each colour.
+fn histogram(colours: &[usize]) -> Vec<usize> {
+    let mut counts = vec![0; colours.len()];
+
+    for colour in colours {
+        counts[*colour] += 1;
+    }
+
+    counts
+}
+
+fn find(parents: &mut [usize], vertex: usize) -> usize {
+    let mut root = vertex;
+
+    while parents[root] != root {
+        root = parents[root];
+    }
+
+    let mut vertex = vertex;
+
+    while parents[vertex] != root {
+        let next = parents[vertex];
+        parents[vertex] = root;
+        vertex = next;
+    }
+
+    root
+}
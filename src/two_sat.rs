@@ -0,0 +1,79 @@
+//! 2-SAT
+//!
+//! A formula with at most two literals per clause is satisfiable exactly when no literal is in the same strongly connected component of its implication graph as its complement.
+//! A model assigns each literal true when its component comes after its complement's in topological order.
+
+use crate::{equivalence::ImplicationGraph, Assignment, Formula};
+
+/// Solves a formula in which every clause has at most two literals, in time linear in its size.
+///
+/// Parity and cardinality constraints are replaced by their own clauses, which must also have at most two literals.
+///
+/// Returns `None` if the formula is unsatisfiable. Otherwise, the assignment assigns every variable in the formula.
+///
+/// # Panics
+///
+/// If some clause has more than two literals, or some constraint needs such a clause.
+pub fn solve_2sat(formula: &Formula) -> Option<Assignment> {
+    let mut graph = ImplicationGraph::default();
+    let mut clauses = formula.clauses().clone();
+
+    // Checked before expanding, as long constraints have exponentially many clauses
+    for xor in formula.xors() {
+        if xor.len() > 2 {
+            panic!("solve_2sat given a parity constraint with more than two variables");
+        }
+
+        clauses.extend(xor.clauses());
+    }
+
+    for cardinality in formula.cardinalities() {
+        if cardinality.bound() >= 2 && cardinality.literals().len() > cardinality.bound() {
+            panic!("solve_2sat given a cardinality constraint with a bound of more than one");
+        }
+
+        clauses.extend(cardinality.clauses());
+    }
+
+    for clause in &clauses {
+        match clause.literals()[..] {
+            [] => return None,
+            // A unit clause `a` is the clause `a | a`
+            [a] => graph.add_binary(a, a),
+            [a, b] => graph.add_binary(a, b),
+            _ => panic!("solve_2sat given a clause with more than two literals"),
+        }
+    }
+
+    // Components are in reverse topological order, so this is the position of each literal's component in that order
+    let mut positions = Vec::new();
+
+    for (position, component) in graph.components().into_iter().enumerate() {
+        for lit in component {
+            if positions.len() <= lit.code() {
+                positions.resize(lit.code() + 1, None);
+            }
+
+            positions[lit.code()] = Some(position);
+        }
+    }
+
+    let position = |code: usize| positions.get(code).copied().flatten();
+    let mut assignment = Assignment::new();
+
+    for var in formula.vars() {
+        let positive = position(var.positive().code());
+        let negative = position(var.negative().code());
+
+        let value = match (positive, negative) {
+            (Some(positive), Some(negative)) if positive == negative => return None,
+            (Some(positive), Some(negative)) => positive < negative,
+            // Neither literal has an implication, so the variable is unconstrained
+            _ => false,
+        };
+
+        assignment.set(var, value);
+    }
+
+    Some(assignment)
+}